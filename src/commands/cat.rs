@@ -1,38 +1,238 @@
-use std::fs;
-use std::io;
+use super::options::{Arg, Parser};
+use super::strerror;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+/// Read buffer size for files that need per-byte processing.
+const BUF_SIZE: usize = 64 * 1024;
+
+/// Output transformations selected by `cat`'s options.
+#[derive(Default)]
+struct CatOptions {
+    number: bool,
+    number_nonblank: bool,
+    squeeze_blank: bool,
+    show_ends: bool,
+    show_tabs: bool,
+    show_nonprinting: bool,
+}
+
+impl CatOptions {
+    fn is_plain(&self) -> bool {
+        !(self.number
+            || self.number_nonblank
+            || self.squeeze_blank
+            || self.show_ends
+            || self.show_tabs
+            || self.show_nonprinting)
+    }
+}
+
+const USAGE: &str = "\
+Usage: cat [OPTION]... [FILE]...
+Concatenate FILE(s) to standard output.
+With no FILE, or when FILE is -, read standard input.
+
+  -A, --show-all           equivalent to -vET
+  -b, --number-nonblank    number nonempty output lines, overrides -n
+  -e                       equivalent to -vE
+  -E, --show-ends          display $ at end of each line
+  -n, --number             number all output lines
+  -s, --squeeze-blank      suppress repeated empty output lines
+  -t                       equivalent to -vT
+  -T, --show-tabs          display TAB characters as ^I
+  -u                       (ignored)
+  -v, --show-nonprinting   use ^ and M- notation, except for LFD and TAB
+      --help               display this help and exit
+      --version            output version information and exit
+";
+
+const LONG_ALIASES: &[(&str, char)] = &[
+    ("show-all", 'A'),
+    ("number-nonblank", 'b'),
+    ("show-ends", 'E'),
+    ("number", 'n'),
+    ("squeeze-blank", 's'),
+    ("show-tabs", 'T'),
+    ("show-nonprinting", 'v'),
+];
+
+/// Line counter and blank-line state, carried across all files like GNU `cat`.
+#[derive(Default)]
+struct State {
+    line: u64,
+    prev_blank: bool,
+    mid_line: bool,
+}
+
 /// Prints the contents of files to standard output, or reads from stdin if no files provided.
 ///
 /// # Arguments
 ///
-/// * `args` - Vector of filenames. If empty, reads from stdin.
+/// * `args` - Vector of options and filenames. If no files are given, or
+///   a file is `-`, stdin is read at that position.
+///
+/// # Options
+///
+/// - `-n`: number all output lines; `-b`: number nonblank lines only.
+/// - `-s`: squeeze repeated empty lines into one.
+/// - `-E`: show `$` at the end of each line.
+/// - `-T`: show tabs as `^I`.
+/// - `-v`: show nonprinting bytes with `^` and `M-` notation.
+/// - `-A` (`-vET`), `-e` (`-vE`), `-t` (`-vT`).
+/// - GNU long forms such as `--number`, and `--help`.
 ///
 /// # Behavior
 ///
-/// - Reads and prints entire file content for each file argument.
-/// - If no files are given, reads lines from stdin and prints them until EOF.
+/// - Files are streamed through a fixed-size buffer, so files of any size
+///   and binary files are copied unchanged, with no trailing newline added.
 ///
 /// # Example
 ///
 /// ```
 /// cat(vec!["file.txt".to_string()]);
+/// cat(vec!["-n".to_string(), "a.txt".to_string(), "-".to_string()]);
 /// cat(vec![]); // reads from stdin
 /// ```
-pub fn cat(args:Vec<String>){
-    for file in &args {
-            match fs::read_to_string(&file){
-                Ok(contents)=>println!("{}",contents),
-                Err(e) =>eprintln!("cat: {file}: {e}"),
-            };
-    }
-    if  args.len()==0{
-         loop {
-        let mut line = String::new();
-        let bytes = io::stdin().read_line(&mut line).unwrap();
-        if bytes == 0 {
-            break;
+pub fn cat(args: Vec<String>) -> i32 {
+    let (opts, mut files) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(status) => return status,
+    };
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut state = State::default();
+    let mut status = 0;
+
+    for file in &files {
+        // `io::copy` between concrete file, pipe and stdio handles lets the
+        // kernel move the data (`copy_file_range`, `sendfile`, `splice`).
+        let result = match (file == "-", opts.is_plain()) {
+            (true, true) => io::copy(&mut io::stdin().lock(), &mut out).map(|_| ()),
+            (true, false) => write_contents(&mut io::stdin().lock(), &mut out, &opts, &mut state),
+            (false, true) => File::open(file)
+                .and_then(|mut f| io::copy(&mut f, &mut out))
+                .map(|_| ()),
+            (false, false) => File::open(file)
+                .and_then(|mut f| write_contents(&mut f, &mut out, &opts, &mut state)),
+        };
+        match result {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return 1,
+            Err(e) => {
+                eprintln!("cat: {file}: {}", strerror(&e));
+                status = 1;
+            }
         }
-        print!("{line}");
     }
+    out.flush().ok();
+    status
+}
+
+fn parse_args(args: Vec<String>) -> Result<(CatOptions, Vec<String>), i32> {
+    let mut opts = CatOptions::default();
+    let mut files = Vec::new();
+
+    let mut parser = Parser::new("cat", USAGE, args).aliases(LONG_ALIASES);
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Arg::Operand(file) => files.push(file),
+            Arg::Short(c) => match c {
+                'n' => opts.number = true,
+                'b' => opts.number_nonblank = true,
+                's' => opts.squeeze_blank = true,
+                'E' => opts.show_ends = true,
+                'T' => opts.show_tabs = true,
+                'v' => opts.show_nonprinting = true,
+                'A' => {
+                    opts.show_nonprinting = true;
+                    opts.show_ends = true;
+                    opts.show_tabs = true;
+                }
+                'e' => {
+                    opts.show_nonprinting = true;
+                    opts.show_ends = true;
+                }
+                't' => {
+                    opts.show_nonprinting = true;
+                    opts.show_tabs = true;
+                }
+                'u' => {}
+                _ => return Err(parser.unexpected(Arg::Short(c))),
+            },
+            arg => return Err(parser.unexpected(arg)),
+        }
+    }
+    Ok((opts, files))
+}
+
+/// Applies the line-based options to `input`, one fixed-size chunk at a
+/// time, so memory use does not depend on line length.
+fn write_contents(
+    input: &mut dyn Read,
+    out: &mut dyn Write,
+    opts: &CatOptions,
+    state: &mut State,
+) -> io::Result<()> {
+    let mut buf = vec![0; BUF_SIZE];
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &b in &buf[..n] {
+            if !state.mid_line {
+                let blank = b == b'\n';
+                if blank && opts.squeeze_blank && state.prev_blank {
+                    continue;
+                }
+                state.prev_blank = blank;
+                if opts.number_nonblank {
+                    if !blank {
+                        state.line += 1;
+                        write!(out, "{:>6}\t", state.line)?;
+                    }
+                } else if opts.number {
+                    state.line += 1;
+                    write!(out, "{:>6}\t", state.line)?;
+                }
+                state.mid_line = true;
+            }
+            if b == b'\n' {
+                if opts.show_ends {
+                    out.write_all(b"$")?;
+                }
+                out.write_all(b"\n")?;
+                state.mid_line = false;
+            } else {
+                write_byte(out, b, opts)?;
+            }
+        }
+    }
+}
+
+/// Writes one byte, applying `-T` and `-v` notation.
+fn write_byte(out: &mut dyn Write, b: u8, opts: &CatOptions) -> io::Result<()> {
+    if b == b'\t' {
+        return out.write_all(if opts.show_tabs { b"^I" } else { b"\t" });
+    }
+    if !opts.show_nonprinting {
+        return out.write_all(&[b]);
+    }
+    let mut b = b;
+    if b >= 128 {
+        out.write_all(b"M-")?;
+        b -= 128;
+    }
+    match b {
+        0..=31 => out.write_all(&[b'^', b + 64]),
+        127 => out.write_all(b"^?"),
+        _ => out.write_all(&[b]),
     }
 }
-// when we enter only cat command we should enable the user to write in the stdi ------- done
\ No newline at end of file
@@ -1,15 +1,32 @@
+use super::options::{Arg, Parser};
+use super::pwd;
 use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+const USAGE: &str = "\
+Usage: cd [-L|-P] [DIR]
+Change the current directory to DIR, $HOME by default.
+
+  -L        follow symbolic links, keeping them in $PWD (default)
+  -P        resolve symbolic links before changing directory
+  --help    display this help and exit
+";
+
 /// Changes the current working directory.
 ///
 /// # Arguments
 ///
 /// * `args` - Vector of arguments:
 ///   - If empty, changes to directory specified by $HOME.
-///   - If contains exactly one element, changes to that directory.
-///   - Otherwise, prints an error.
+///   - `-` changes to `$OLDPWD` and prints the new directory.
+///   - `-L` follows symlinks logically (default), `-P` resolves them.
+///   - Otherwise, changes to the single directory given.
 ///
 /// # Behavior
 ///
+/// - Relative names that do not start with `.` or `..` are looked up in
+///   each entry of `$CDPATH`; the new directory is printed when found there.
+/// - `PWD` and `OLDPWD` are exported on every successful change.
 /// - Prints errors if changing directory fails or usage is incorrect.
 ///
 /// # Example
@@ -17,25 +34,127 @@ use std::env;
 /// ```
 /// cd(vec![]); // changes to $HOME
 /// cd(vec!["/tmp".to_string()]); // changes to /tmp
+/// cd(vec!["-".to_string()]); // back to the previous directory
 /// ```
-pub fn cd(args: Vec<String>) {
-    if args.is_empty() {
-        if let Some(h) = env::var_os("HOME") {
-            if let Err(e) = env::set_current_dir(h) {
-                eprintln!("cd: {}",  e);
-            }
-        } else {
-            eprintln!("cd: Unable to determine home directory");
+pub fn cd(args: Vec<String>) -> i32 {
+    let mut physical = false;
+    let mut operands = Vec::new();
+
+    let mut parser = Parser::new("cd", USAGE, args);
+    loop {
+        match parser.next() {
+            Ok(Some(Arg::Short('L'))) => physical = false,
+            Ok(Some(Arg::Short('P'))) => physical = true,
+            Ok(Some(Arg::Operand(operand))) => operands.push(operand),
+            Ok(Some(arg)) => return parser.unexpected(arg),
+            Ok(None) => break,
+            Err(status) => return status,
         }
-        return;
     }
-    if args.len() != 1 {
+
+    if operands.len() > 1 {
         eprintln!("cd: expected exactly one argument");
-        return;
+        return 1;
     }
 
-    let path = &args[0];
-    if let Err(e) = env::set_current_dir(path) {
-        eprintln!("cd: {}: {}", path, e);
+    let (target, print) = match operands.first() {
+        None => match env::var("HOME") {
+            Ok(h) => (h, false),
+            Err(_) => {
+                eprintln!("cd: Unable to determine home directory");
+                return 1;
+            }
+        },
+        Some(dir) if dir == "-" => match env::var("OLDPWD") {
+            Ok(old) => (old, true),
+            Err(_) => {
+                eprintln!("cd: OLDPWD not set");
+                return 1;
+            }
+        },
+        Some(dir) => match search_cdpath(dir) {
+            Some(found) => (found, true),
+            None => (dir.to_string(), false),
+        },
+    };
+
+    change_dir(&target, physical, print)
+}
+
+/// Changes to `target` and updates `PWD`/`OLDPWD`.
+///
+/// With `physical` the new `PWD` has all symlinks resolved; otherwise it is
+/// the lexical join of the old `PWD` and `target`. When `print` is set the
+/// new directory is written to stdout, as done for `cd -` and `CDPATH` hits.
+pub fn change_dir(target: &str, physical: bool, print: bool) -> i32 {
+    let old = pwd::logical();
+
+    let new_pwd = if physical {
+        if let Err(e) = env::set_current_dir(target) {
+            eprintln!("cd: {}: {}", target, e);
+            return 1;
+        }
+        match env::current_dir().and_then(fs::canonicalize) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("cd: {}", e);
+                return 1;
+            }
+        }
+    } else {
+        let logical = normalize(&Path::new(&old).join(target));
+        if let Err(e) = env::set_current_dir(&logical) {
+            eprintln!("cd: {}: {}", target, e);
+            return 1;
+        }
+        logical
+    };
+
+    env::set_var("OLDPWD", &old);
+    env::set_var("PWD", &new_pwd);
+    if print {
+        println!("{}", new_pwd.display());
+    }
+    0
+}
+
+/// Looks `dir` up in `$CDPATH`, returning the first entry that holds it.
+///
+/// Absolute names and names starting with `.` or `..` are never searched.
+fn search_cdpath(dir: &str) -> Option<String> {
+    if dir.starts_with('/') || dir == "." || dir == ".." {
+        return None;
+    }
+    if dir.starts_with("./") || dir.starts_with("../") {
+        return None;
+    }
+    let cdpath = env::var("CDPATH").ok()?;
+    for entry in cdpath.split(':') {
+        if entry.is_empty() || entry == "." {
+            continue;
+        }
+        let candidate = Path::new(entry).join(dir);
+        if candidate.is_dir() {
+            return Some(candidate.display().to_string());
+        }
+    }
+    None
+}
+
+/// Lexically resolves `.` and `..` components without touching the disk.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    if out.as_os_str().is_empty() {
+        out.push("/");
     }
+    out
 }
@@ -1,3 +1,10 @@
+use super::options::help_or_version;
+
+const USAGE: &str = "\
+Usage: clear
+Clear the terminal screen.
+";
+
 /// Clears the terminal screen by printing ANSI escape codes.
 ///
 /// # Arguments
@@ -6,7 +13,7 @@
 ///
 /// # Behavior
 ///
-/// - Prints an error if any options are passed.
+/// - Answers `--help` and `--version`; prints an error for any other options.
 /// - Clears the screen and moves the cursor to the top-left corner.
 ///
 /// # Example
@@ -15,7 +22,10 @@
 /// clear(vec![]);
 /// ```
 pub fn clear(a:Vec<String>){
-if a.len() != 0{
+if help_or_version("clear", USAGE, &a).is_some(){
+    return;
+}
+if !a.is_empty(){
     eprintln!("clear doesnt work with options");
     return;
 }
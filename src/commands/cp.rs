@@ -1,95 +1,466 @@
-use std::fs;
-/// Copies a file from source to destination.
+use super::confirm;
+use super::options::{Arg, Parser};
+use super::strerror;
+use std::fs::{self, File, FileTimes, Metadata, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{lchown, symlink, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// Options controlling how [`copy_path`] copies files and trees.
+#[derive(Default, Clone)]
+pub struct CopyOptions {
+    /// Copy directories recursively (`-r`, `-R`).
+    pub recursive: bool,
+    /// Keep mode, ownership and timestamps (`-p`).
+    pub preserve: bool,
+    /// Keep extended attributes as well (`-a`).
+    pub xattrs: bool,
+    /// Ask before overwriting (`-i`).
+    pub interactive: bool,
+    /// Never overwrite an existing file (`-n`).
+    pub no_clobber: bool,
+    /// Remove a destination that cannot be opened and retry (`-f`).
+    pub force: bool,
+    /// Print each copied path (`-v`).
+    pub verbose: bool,
+    /// `Some(true)` always follows symlinks (`-L`), `Some(false)` never does
+    /// (`-P`). `None` follows them only for operands of a non-recursive copy.
+    pub dereference: Option<bool>,
+}
+
+const USAGE: &str = "\
+Usage: cp [OPTION]... SOURCE DEST
+  or:  cp [OPTION]... SOURCE... DIRECTORY
+Copy SOURCE to DEST, or multiple SOURCE(s) to DIRECTORY.
+
+  -a, --archive                same as -R -P -p, also keeping xattrs
+  -f, --force                  if a destination file cannot be opened,
+                               remove it and try again
+  -i, --interactive            prompt before overwrite
+  -L, --dereference            always follow symbolic links in SOURCE
+  -n, --no-clobber             do not overwrite an existing file
+  -P, --no-dereference         never follow symbolic links in SOURCE
+  -p                           preserve mode, ownership and timestamps
+  -R, -r, --recursive          copy directories recursively
+  -v, --verbose                explain what is being done
+      --help                   display this help and exit
+      --version                output version information and exit
+";
+
+const LONG_ALIASES: &[(&str, char)] = &[
+    ("archive", 'a'),
+    ("force", 'f'),
+    ("interactive", 'i'),
+    ("dereference", 'L'),
+    ("no-clobber", 'n'),
+    ("no-dereference", 'P'),
+    ("recursive", 'r'),
+    ("verbose", 'v'),
+];
+
+/// Copies files and directories.
 ///
 /// # Arguments
 ///
-/// * `args` - Vector of arguments where:
-///   - args[0]: source file path
-///   - args[1]: destination file or directory path
+/// * `args` - Vector of arguments: options, then one or more sources and a
+///   destination. With several sources the destination must be a directory.
+///
+/// # Options
+///
+/// - `-r`, `-R`: copy directories recursively.
+/// - `-p`: preserve mode, ownership and timestamps.
+/// - `-a`: like `-R -P -p`, also preserving extended attributes.
+/// - `-i`: prompt before overwriting; `-n`: never overwrite; `-f`: remove
+///   a destination that cannot be opened and try again.
+/// - `-v`: print `'src' -> 'dst'` for every copied path.
+/// - `-L`: always follow symlinks in sources; `-P`: never follow them.
+/// - GNU long forms such as `--recursive`, and `--help`.
 ///
 /// # Behavior
 ///
-/// - Does not support copying directories recursively.
-/// - If destination is a directory, copies source file inside it.
-/// - Checks and prevents copying a file onto itself.
-/// - Prints errors on failure.
+/// - If destination is a directory, sources are copied inside it.
+/// - Refuses to copy a file onto itself or a directory into itself.
+/// - Prints errors on failure and continues with the next source.
 ///
 /// # Example
 ///
 /// ```
 /// cp(vec!["file.txt".to_string(), "backup/".to_string()]);
-/// cp(vec!["file.txt".to_string(), "copy.txt".to_string()]);
+/// cp(vec!["-r".to_string(), "src".to_string(), "a.txt".to_string(), "dst".to_string()]);
 /// ```
-pub fn cp(args: Vec<String>) {
-    if args.len() != 2 {
+pub fn cp(args: Vec<String>) -> i32 {
+    let (opts, operands) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(status) => return status,
+    };
+
+    if operands.len() < 2 {
         eprintln!("cp: missing source or destination");
-        return;
+        return 1;
     }
 
-    let src = &args[0];
-    let dst = &args[1];
+    let (sources, target) = operands.split_at(operands.len() - 1);
+    let target = Path::new(&target[0]);
+    let into_dir = target.is_dir();
+    if sources.len() > 1 && !into_dir {
+        eprintln!("cp: target '{}' is not a directory", target.display());
+        return 1;
+    }
 
-    match fs::metadata(&src) {
-        Ok(meta) => {
-            if meta.is_dir() {
-                eprintln!(
-                    "cp: '{}' is a directory (would ve used -r but not implemented)",
-                    src
-                );
-                return;
+    let mut status = 0;
+    for src in sources {
+        let src = Path::new(src);
+        let dst = if into_dir {
+            let name = src
+                .file_name()
+                .map(PathBuf::from)
+                .or_else(|| fs::canonicalize(src).ok()?.file_name().map(PathBuf::from))
+                .unwrap_or_default();
+            target.join(name)
+        } else {
+            target.to_path_buf()
+        };
+        if let Err(errors) = copy_path(src, &dst, &opts, true) {
+            for e in errors {
+                eprintln!("cp: {}", strerror(&e));
             }
-        }
-        Err(e) => {
-            eprintln!("cp: cannot access '{}': {}", src, e);
-            return;
+            status = 1;
         }
     }
+    status
+}
+
+fn parse_args(args: Vec<String>) -> Result<(CopyOptions, Vec<String>), i32> {
+    let mut opts = CopyOptions::default();
+    let mut operands = Vec::new();
 
-    match (
-        fs::metadata(&dst),
-        fs::canonicalize(dst),
-        fs::canonicalize(src),
-        std::env::current_dir(),
-    ) {
-        (Ok(meta), Ok(abs_dst_path), Ok(abs_src_path), Ok(cdr)) => {
-            if abs_dst_path == cdr || abs_dst_path == abs_src_path {
-                println!("You have been try to copy the same file!");
-                return;
+    let mut parser = Parser::new("cp", USAGE, args).aliases(LONG_ALIASES);
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Arg::Operand(operand) => operands.push(operand),
+            Arg::Short('r' | 'R') => opts.recursive = true,
+            Arg::Short('p') => opts.preserve = true,
+            Arg::Short('a') => {
+                opts.recursive = true;
+                opts.preserve = true;
+                opts.xattrs = true;
+                opts.dereference = Some(false);
+            }
+            Arg::Short('i') => {
+                opts.interactive = true;
+                opts.no_clobber = false;
             }
+            Arg::Short('n') => {
+                opts.no_clobber = true;
+                opts.interactive = false;
+            }
+            Arg::Short('f') => opts.force = true,
+            Arg::Short('v') => opts.verbose = true,
+            Arg::Short('L') => opts.dereference = Some(true),
+            Arg::Short('P') => opts.dereference = Some(false),
+            arg => return Err(parser.unexpected(arg)),
+        }
+    }
+    Ok((opts, operands))
+}
 
-            if meta.is_dir() {
-                if let Err(e) = std::env::set_current_dir(dst) {
-                    eprintln!("{}", e);
-                    return;
-                }
+/// Copies `src` to `dst`, descending into directories when recursive.
+///
+/// `top` marks a command-line operand, which decides whether symlinks are
+/// followed when [`CopyOptions::dereference`] is unset. Like GNU `cp -r`, a
+/// failure inside a directory does not stop the copy: the rest of the tree
+/// is still copied and every error is returned, in the order they happened.
+/// Errors carry the offending path in their message.
+pub fn copy_path(
+    src: &Path,
+    dst: &Path,
+    opts: &CopyOptions,
+    top: bool,
+) -> Result<(), Vec<io::Error>> {
+    let mut errors = Vec::new();
+    if let Err(e) = copy_entry(src, dst, opts, top, &mut errors) {
+        errors.push(e);
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
 
-                if let Err(e) = fs::File::create(src) {
-                    eprintln!("{}", e);
-                    return;
-                }
-                if let Err(e) = std::env::set_current_dir("..") {
-                    eprintln!("{}", e);
-                    return;
-                }
-                if let Err(e) = fs::copy(&src, format!("{}/{}", dst, src)) {
-                    eprintln!("{}", e);
-                    return;
-                }
+/// Copies one entry for [`copy_path`]. Its own failure is returned, while
+/// failures of entries below a directory are added to `errors`.
+fn copy_entry(
+    src: &Path,
+    dst: &Path,
+    opts: &CopyOptions,
+    top: bool,
+    errors: &mut Vec<io::Error>,
+) -> io::Result<()> {
+    let follow = opts.dereference.unwrap_or(top && !opts.recursive);
+    let meta = if follow {
+        fs::metadata(src)
+    } else {
+        fs::symlink_metadata(src)
+    }
+    .map_err(|e| context(e, format!("cannot stat '{}'", src.display())))?;
 
-                return;
-            } else {
-                if let Err(e) = fs::copy(&src, dst) {
-                    eprintln!("cp: error copying '{}': {}", src, e);
-                }
+    let dst_meta = fs::symlink_metadata(dst).ok();
+    if let Ok(dm) = fs::metadata(dst) {
+        if dm.dev() == meta.dev() && dm.ino() == meta.ino() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{}' and '{}' are the same file", src.display(), dst.display()),
+            ));
+        }
+    }
+
+    if meta.is_dir() {
+        if !opts.recursive {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("-r not specified; omitting directory '{}'", src.display()),
+            ));
+        }
+        copy_dir(src, dst, &meta, dst_meta.as_ref(), opts, errors)
+    } else {
+        if dst_meta.is_some() && !may_overwrite(dst, opts) {
+            return Ok(());
+        }
+        if meta.file_type().is_symlink() {
+            let link = fs::read_link(src)
+                .map_err(|e| context(e, format!("cannot read symbolic link '{}'", src.display())))?;
+            if dst_meta.is_some() {
+                fs::remove_file(dst)
+                    .map_err(|e| context(e, format!("cannot remove '{}'", dst.display())))?;
+            }
+            symlink(&link, dst).map_err(|e| {
+                context(e, format!("cannot create symbolic link '{}'", dst.display()))
+            })?;
+        } else if meta.is_file() {
+            copy_file(src, dst, opts, dst_meta.is_some())?;
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("cannot copy special file '{}'", src.display()),
+            ));
+        }
+        if opts.verbose {
+            println!("'{}' -> '{}'", src.display(), dst.display());
+        }
+        if opts.preserve {
+            preserve_metadata(&meta, dst, opts.xattrs, src)?;
+        }
+        Ok(())
+    }
+}
+
+fn copy_dir(
+    src: &Path,
+    dst: &Path,
+    meta: &Metadata,
+    dst_meta: Option<&Metadata>,
+    opts: &CopyOptions,
+    errors: &mut Vec<io::Error>,
+) -> io::Result<()> {
+    if let (Ok(abs_src), Some(parent)) = (fs::canonicalize(src), dst.parent()) {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(abs_parent) = fs::canonicalize(parent) {
+            if abs_parent.starts_with(&abs_src) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "cannot copy a directory, '{}', into itself, '{}'",
+                        src.display(),
+                        dst.display()
+                    ),
+                ));
+            }
+        }
+    }
+
+    match dst_meta {
+        Some(dm) if dm.is_dir() => {}
+        Some(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "cannot overwrite non-directory '{}' with directory '{}'",
+                    dst.display(),
+                    src.display()
+                ),
+            ))
+        }
+        None => {
+            fs::create_dir(dst)
+                .map_err(|e| context(e, format!("cannot create directory '{}'", dst.display())))?;
+            if opts.verbose {
+                println!("'{}' -> '{}'", src.display(), dst.display());
             }
         }
-        (Err(e), _, _, _) => {
-            eprintln!("cp: cannot access '{}': {}", src, e);
-            return;
+    }
+
+    let entries = fs::read_dir(src)
+        .map_err(|e| context(e, format!("cannot open directory '{}'", src.display())))?;
+    for entry in entries {
+        let copied = entry
+            .map_err(|e| context(e, format!("cannot read directory '{}'", src.display())))
+            .and_then(|entry| {
+                let dst = dst.join(entry.file_name());
+                copy_entry(&entry.path(), &dst, opts, false, errors)
+            });
+        if let Err(e) = copied {
+            errors.push(e);
+        }
+    }
+
+    if opts.preserve {
+        preserve_metadata(meta, dst, opts.xattrs, src)?;
+    }
+    Ok(())
+}
+
+/// Copies a regular file, never leaving a new, partial `dst` behind.
+///
+/// With `-f`, a `dst` that cannot be opened for writing is removed and
+/// opened again; a source that cannot be read never touches `dst`.
+fn copy_file(src: &Path, dst: &Path, opts: &CopyOptions, exists: bool) -> io::Result<()> {
+    let mut reader = File::open(src)
+        .map_err(|e| context(e, format!("cannot open '{}' for reading", src.display())))?;
+    let meta = reader.metadata()?;
+    let create_err = |e| context(e, format!("cannot create regular file '{}'", dst.display()));
+
+    let mut writer = match create(dst, &meta) {
+        Err(_) if exists && opts.force => fs::remove_file(dst)
+            .and_then(|_| create(dst, &meta))
+            .map_err(create_err)?,
+        other => other.map_err(create_err)?,
+    };
+    let result = copy_data(&mut reader, &meta, &mut writer);
+    if result.is_err() && (!exists || opts.force) {
+        fs::remove_file(dst).ok();
+    }
+    result.map_err(|e| {
+        let what = format!("error copying '{}' to '{}'", src.display(), dst.display());
+        context(e, what)
+    })
+}
+
+/// Opens `dst` for writing, truncating it, with the permission bits of
+/// `meta` if it is created.
+fn create(dst: &Path, meta: &Metadata) -> io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(meta.mode() & 0o7777)
+        .open(dst)
+}
+
+/// Copies the contents and permission bits of `reader`, whose metadata is
+/// `meta`, into `writer`.
+///
+/// The data never passes through a user-space buffer: the copy is a
+/// reflink (`FICLONE`) where the filesystem supports it, otherwise the
+/// kernel copies it with `copy_file_range`. Holes in sparse files are
+/// skipped so the copy stays sparse.
+fn copy_data(reader: &mut File, meta: &Metadata, writer: &mut File) -> io::Result<()> {
+    // SAFETY: both descriptors belong to `File`s that are open and owned
+    // by this function for the whole call; FICLONE only reads them.
+    let cloned = unsafe { libc::ioctl(writer.as_raw_fd(), libc::FICLONE, reader.as_raw_fd()) };
+    if cloned != 0 {
+        if meta.blocks() * 512 < meta.len() {
+            copy_sparse(reader, writer, meta.len())?;
+        } else {
+            io::copy(reader, writer)?;
+        }
+    }
+    writer.set_permissions(meta.permissions())
+}
+
+/// Copies only the data segments of `reader`, found with `SEEK_DATA` and
+/// `SEEK_HOLE`, then extends `writer` to `len` to recreate trailing holes.
+fn copy_sparse(reader: &File, writer: &mut File, len: u64) -> io::Result<()> {
+    let fd = reader.as_raw_fd();
+    let mut pos: i64 = 0;
+    while (pos as u64) < len {
+        // SAFETY: `fd` comes from `reader`, which is borrowed and so stays
+        // open for the call; lseek only moves its file offset.
+        let data = unsafe { libc::lseek(fd, pos, libc::SEEK_DATA) };
+        if data < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENXIO) {
+                break;
+            }
+            // SEEK_DATA is not supported here: copy everything that is left.
+            (&*reader).seek(SeekFrom::Start(pos as u64))?;
+            writer.seek(SeekFrom::Start(pos as u64))?;
+            io::copy(&mut &*reader, writer)?;
+            return Ok(());
         }
-        _ => {
-            eprintln!("cp: Error");
-            return;
+        // SAFETY: as above, `reader` keeps `fd` open.
+        let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+        let hole = if hole < 0 { len as i64 } else { hole };
+
+        (&*reader).seek(SeekFrom::Start(data as u64))?;
+        writer.seek(SeekFrom::Start(data as u64))?;
+        io::copy(&mut reader.take((hole - data) as u64), writer)?;
+        pos = hole;
+    }
+    writer.set_len(len)
+}
+
+/// Returns whether an existing `dst` may be replaced, asking with `-i`.
+fn may_overwrite(dst: &Path, opts: &CopyOptions) -> bool {
+    if opts.no_clobber {
+        return false;
+    }
+    if opts.interactive {
+        return confirm(&format!("cp: overwrite '{}'?", dst.display()));
+    }
+    true
+}
+
+/// Copies ownership, mode, timestamps and optionally xattrs from `meta`
+/// (the metadata of `src`) onto `dst`.
+///
+/// Failing to change ownership is ignored, as only root may give files away.
+pub fn preserve_metadata(meta: &Metadata, dst: &Path, xattrs: bool, src: &Path) -> io::Result<()> {
+    if lchown(dst, Some(meta.uid()), Some(meta.gid())).is_err() {
+        lchown(dst, None, Some(meta.gid())).ok();
+    }
+
+    if xattrs {
+        if let Ok(names) = xattr::list(src) {
+            for name in names {
+                if let Ok(Some(value)) = xattr::get(src, &name) {
+                    xattr::set(dst, &name, &value).ok();
+                }
+            }
         }
     }
+
+    if meta.file_type().is_symlink() {
+        return Ok(());
+    }
+
+    let err = |e| context(e, format!("preserving times for '{}'", dst.display()));
+    let times = FileTimes::new()
+        .set_accessed(meta.accessed().map_err(err)?)
+        .set_modified(meta.modified().map_err(err)?);
+    fs::File::open(dst)
+        .and_then(|f| f.set_times(times))
+        .map_err(err)?;
+    fs::set_permissions(dst, fs::Permissions::from_mode(meta.mode()))
+        .map_err(|e| context(e, format!("preserving permissions for '{}'", dst.display())))
+}
+
+/// Prefixes an I/O error with a description of what was being attempted.
+fn context(e: io::Error, what: String) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", what, strerror(&e)))
 }
@@ -0,0 +1,296 @@
+use super::options::help_or_version;
+use super::{cd, pwd};
+use crate::prompt::tilde_abbrev;
+use std::sync::Mutex;
+
+/// Saved directories, most recent first. The current directory is always
+/// entry 0 of the stack and is not stored here.
+static STACK: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+const PUSHD_USAGE: &str = "\
+Usage: pushd [DIR | +N | -N]
+Add DIR to the top of the directory stack and change to it, or rotate
+the stack so that entry N becomes the top.
+";
+
+/// Pushes the current directory onto the stack and changes to a new one.
+///
+/// # Arguments
+///
+/// * `args` - Vector of arguments:
+///   - `dir`: save the current directory, then `cd` into `dir`.
+///   - `+N` / `-N`: rotate the stack so that the Nth entry, counted from
+///     the left (`+`) or right (`-`) of `dirs`, becomes the top.
+///   - If empty, swaps the top two entries.
+///
+/// # Behavior
+///
+/// - Prints the resulting stack like `dirs` on success.
+///
+/// # Example
+///
+/// ```
+/// pushd(vec!["/tmp".to_string()]);
+/// pushd(vec!["+1".to_string()]);
+/// ```
+pub fn pushd(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("pushd", PUSHD_USAGE, &args) {
+        return status;
+    }
+    if args.len() > 1 {
+        eprintln!("pushd: too many arguments");
+        return 1;
+    }
+
+    let status = match args.first() {
+        None => {
+            if STACK.lock().unwrap().is_empty() {
+                eprintln!("pushd: no other directory");
+                return 1;
+            }
+            swap_top()
+        }
+        Some(arg) if is_index(arg) => {
+            let full = full_stack();
+            match index(arg, full.len()) {
+                Some(n) => rotate(n),
+                None => {
+                    eprintln!("pushd: {}: directory stack index out of range", arg);
+                    1
+                }
+            }
+        }
+        Some(dir) => {
+            let old = pwd::logical();
+            let status = cd::change_dir(dir, false, false);
+            if status == 0 {
+                STACK.lock().unwrap().insert(0, old);
+            }
+            status
+        }
+    };
+
+    if status == 0 {
+        print_stack(false, false, false);
+    }
+    status
+}
+
+const POPD_USAGE: &str = "\
+Usage: popd [+N | -N]
+Remove the top entry, or entry N, from the directory stack.
+";
+
+/// Removes an entry from the directory stack.
+///
+/// # Arguments
+///
+/// * `args` - Vector of arguments:
+///   - If empty, removes the top entry and changes to the new top.
+///   - `+N` / `-N`: removes the Nth entry counted from the left or right
+///     of `dirs`; `+0` behaves like no argument.
+///
+/// # Example
+///
+/// ```
+/// popd(vec![]);
+/// popd(vec!["+2".to_string()]);
+/// ```
+pub fn popd(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("popd", POPD_USAGE, &args) {
+        return status;
+    }
+    if args.len() > 1 {
+        eprintln!("popd: too many arguments");
+        return 1;
+    }
+    if STACK.lock().unwrap().is_empty() {
+        eprintln!("popd: directory stack empty");
+        return 1;
+    }
+
+    let n = match args.first() {
+        None => 0,
+        Some(arg) if is_index(arg) => match index(arg, full_stack().len()) {
+            Some(n) => n,
+            None => {
+                eprintln!("popd: {}: directory stack index out of range", arg);
+                return 1;
+            }
+        },
+        Some(arg) => {
+            eprintln!("popd: {}: invalid argument", arg);
+            return 1;
+        }
+    };
+
+    if n == 0 {
+        let top = STACK.lock().unwrap()[0].clone();
+        let status = cd::change_dir(&top, false, false);
+        if status != 0 {
+            return status;
+        }
+        STACK.lock().unwrap().remove(0);
+    } else {
+        STACK.lock().unwrap().remove(n - 1);
+    }
+
+    print_stack(false, false, false);
+    0
+}
+
+const DIRS_USAGE: &str = "\
+Usage: dirs [-clpv] [+N | -N]
+Display the directory stack.
+
+  -c        clear the directory stack
+  -l        do not abbreviate $HOME as ~
+  -p        print one entry per line
+  -v        print one entry per line, prefixed with its index
+";
+
+/// Displays the directory stack.
+///
+/// # Arguments
+///
+/// * `args` - Vector of options:
+///   - `-c`: clear the stack.
+///   - `-l`: print full paths instead of abbreviating `$HOME` as `~`.
+///   - `-p`: print one entry per line.
+///   - `-v`: print one entry per line, prefixed with its index.
+///   - `+N` / `-N`: print only the Nth entry.
+///
+/// # Example
+///
+/// ```
+/// dirs(vec!["-v".to_string()]);
+/// ```
+pub fn dirs(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("dirs", DIRS_USAGE, &args) {
+        return status;
+    }
+    let mut long = false;
+    let mut per_line = false;
+    let mut verbose = false;
+    let mut clear = false;
+    let mut only = None;
+
+    for arg in &args {
+        if is_index(arg) {
+            match index(arg, full_stack().len()) {
+                Some(n) => only = Some(n),
+                None => {
+                    eprintln!("dirs: {}: directory stack index out of range", arg);
+                    return 1;
+                }
+            }
+            continue;
+        }
+        if !arg.starts_with('-') {
+            eprintln!("dirs: {}: invalid argument", arg);
+            return 1;
+        }
+        for c in arg.chars().skip(1) {
+            match c {
+                'c' => clear = true,
+                'l' => long = true,
+                'p' => per_line = true,
+                'v' => verbose = true,
+                _ => {
+                    eprintln!("dirs: invalid option -- '{}'", c);
+                    return 2;
+                }
+            }
+        }
+    }
+
+    if clear {
+        STACK.lock().unwrap().clear();
+        return 0;
+    }
+    if let Some(n) = only {
+        let dir = &full_stack()[n];
+        println!("{}", if long { dir.clone() } else { tilde_abbrev(dir) });
+        return 0;
+    }
+    print_stack(long, per_line, verbose);
+    0
+}
+
+/// Resolves a `~N`, `~+N` or `~-N` stack reference to a directory.
+///
+/// `spec` is the text following the `~`, e.g. `"2"`, `"+1"` or `"-0"`.
+pub fn entry(spec: &str) -> Option<String> {
+    let full = full_stack();
+    index(spec, full.len()).map(|n| full[n].clone())
+}
+
+/// Returns `true` if `arg` is a stack index argument: `+N` or `-N`.
+fn is_index(arg: &str) -> bool {
+    arg.strip_prefix(['+', '-'])
+        .is_some_and(|d| !d.is_empty() && d.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Converts `N`, `+N` or `-N` into a position in [`full_stack`].
+fn index(spec: &str, len: usize) -> Option<usize> {
+    let digits = spec.strip_prefix(['+', '-']).unwrap_or(spec);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let n: usize = digits.parse().ok()?;
+    if n >= len {
+        return None;
+    }
+    if spec.starts_with('-') {
+        Some(len - 1 - n)
+    } else {
+        Some(n)
+    }
+}
+
+/// The current directory followed by the saved entries.
+fn full_stack() -> Vec<String> {
+    let mut full = vec![pwd::logical()];
+    full.extend(STACK.lock().unwrap().iter().cloned());
+    full
+}
+
+/// Makes entry `n` the top of the stack, changing to it.
+fn rotate(n: usize) -> i32 {
+    let full = full_stack();
+    let rotated: Vec<String> = full[n..].iter().chain(&full[..n]).cloned().collect();
+    let status = cd::change_dir(&rotated[0], false, false);
+    if status == 0 {
+        *STACK.lock().unwrap() = rotated[1..].to_vec();
+    }
+    status
+}
+
+/// Exchanges the current directory with the top saved entry.
+fn swap_top() -> i32 {
+    let old = pwd::logical();
+    let top = STACK.lock().unwrap()[0].clone();
+    let status = cd::change_dir(&top, false, false);
+    if status == 0 {
+        STACK.lock().unwrap()[0] = old;
+    }
+    status
+}
+
+fn print_stack(long: bool, per_line: bool, verbose: bool) {
+    let entries: Vec<String> = full_stack()
+        .iter()
+        .map(|d| if long { d.clone() } else { tilde_abbrev(d) })
+        .collect();
+    if verbose {
+        for (i, dir) in entries.iter().enumerate() {
+            println!("{:2}  {}", i, dir);
+        }
+    } else if per_line {
+        for dir in &entries {
+            println!("{}", dir);
+        }
+    } else {
+        println!("{}", entries.join(" "));
+    }
+}
@@ -1,122 +1,189 @@
-/// Prints the arguments to stdout with escape sequence interpretation.
+use super::options::help_or_version;
+use std::io::{self, Write};
+
+const USAGE: &str = "\
+Usage: echo [-neE] [STRING]...
+Echo the STRING(s) to standard output.
+
+  -n        do not output the trailing newline
+  -e        enable interpretation of backslash escapes
+  -E        disable interpretation of backslash escapes (default)
+";
+
+/// Prints the arguments to stdout, separated by spaces.
 ///
-/// Supported escape sequences:
-/// - `\n`: newline
-/// - `\t`: tab
-/// - `\r`: carriage return
-/// - `\\`: backslash
-/// - `\"`: double quote
-/// - `\'`: single quote
-/// - `\c`: stop output immediately
+/// Options, accepted only before the first other argument:
+/// - `-n`: do not print the trailing newline
+/// - `-e`: interpret backslash escapes (see [`unescape`])
+/// - `-E`: do not interpret backslash escapes (default)
 ///
 /// # Arguments
 ///
-/// * `cmd` - Cmd struct containing the command name and arguments.
+/// * `args` - The tokenized, expanded arguments following `echo`.
 ///
 /// # Example
 ///
 /// ```
-/// let cmd = Cmd {
-///     command: "echo".to_string(),
-///     args: vec!["hello\\nworld".to_string()],
-/// };
-/// echo(cmd);
+/// echo(vec!["-e".to_string(), "hello\\nworld".to_string()]);
 /// ```
-use std::io::{self, Write};
-
-pub fn echo(raw_input: &str) {
-    let mut args = Vec::new();
-    let mut current_arg = String::new();
-    let mut in_quotes: Option<char> = None;
-
-    let mut chars = raw_input.chars();
-    while let Some(c) = chars.next() {
-        match c {
-            '\\' if in_quotes.is_none() => {
-                if let Some(next_char) = chars.next() {
-                    current_arg.push(next_char);
-                } else {
-                    current_arg.push('\\');
-                }
-            }
-            '"' | '\'' => {
-                if in_quotes == Some(c) {
-                    in_quotes = None;
-                } else if in_quotes.is_none() {
-                    in_quotes = Some(c);
-                } else {
-                    current_arg.push(c);
-                }
-            }
-            ' ' if in_quotes.is_none() => {
-                if !current_arg.is_empty() {
-                    args.push(current_arg);
-                    current_arg = String::new();
-                }
-            }
-            _ => {
-                current_arg.push(c);
+pub fn echo(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("echo", USAGE, &args) {
+        return status;
+    }
+    let mut words = args.iter().peekable();
+    let mut newline = true;
+    let mut escapes = false;
+    while let Some(word) = words.peek() {
+        let flags = match word.strip_prefix('-') {
+            Some(f) if !f.is_empty() && f.chars().all(|c| "neE".contains(c)) => f,
+            _ => break,
+        };
+        for c in flags.chars() {
+            match c {
+                'n' => newline = false,
+                'e' => escapes = true,
+                _ => escapes = false,
             }
         }
+        words.next();
     }
-    if !current_arg.is_empty() {
-        args.push(current_arg);
+
+    let content = words.cloned().collect::<Vec<_>>().join(" ");
+    let mut out = Vec::new();
+    if escapes {
+        if !unescape(&content, &mut out) {
+            newline = false;
+        }
+    } else {
+        out.extend_from_slice(content.as_bytes());
+    }
+    if newline {
+        out.push(b'\n');
     }
 
-    let content: String = args.iter().skip(1).cloned().collect::<Vec<_>>().join(" ");
-    let mut chars = content.chars().peekable();
     let stdout = io::stdout();
     let mut handle = stdout.lock();
+    match handle.write_all(&out).and_then(|_| handle.flush()) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("echo: write error: {}", e);
+            1
+        }
+    }
+}
 
+/// Appends `s` to `out`, interpreting backslash escapes.
+///
+/// Supported escape sequences:
+/// - `\\`: backslash
+/// - `\a`, `\b`, `\e`, `\f`, `\n`, `\r`, `\t`, `\v`: the usual control characters
+/// - `\0NNN`: byte with octal value NNN (up to three digits)
+/// - `\xHH`: byte with hex value HH (up to two digits)
+/// - `\uHHHH`, `\UHHHHHHHH`: Unicode code point, written as UTF-8
+/// - `\c`: stop output immediately
+///
+/// Unknown escapes, and `\u`/`\U` escapes that are not a valid code point,
+/// are written as-is, backslash included.
+///
+/// Returns `false` if `\c` was found, `true` otherwise.
+pub fn unescape(s: &str, out: &mut Vec<u8>) -> bool {
+    let mut chars = s.chars().peekable();
     while let Some(c) = chars.next() {
-        if c == '\\' {
-            match chars.peek() {
-                Some('x') => {
-                    chars.next(); 
-                    let hi = chars.next();
-                    let lo = chars.next();
-                    if let (Some(h), Some(l)) = (hi, lo) {
-                        if let Ok(byte) = u8::from_str_radix(&format!("{}{}", h, l), 16) {
-                            handle.write_all(&[byte]).unwrap();
-                            continue;
-                        }
-                    }
-                    handle.write_all(b"").unwrap();
-                    if let Some(h) = hi {
-                        handle.write_all(&[h as u8]).unwrap();
+        if c != '\\' {
+            let mut buf = [0; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let Some(next) = chars.next() else {
+            out.push(b'\\');
+            break;
+        };
+        match next {
+            '\\' => out.push(b'\\'),
+            'a' => out.push(0x07),
+            'b' => out.push(0x08),
+            'e' | 'E' => out.push(0x1B),
+            'f' => out.push(0x0C),
+            'n' => out.push(b'\n'),
+            'r' => out.push(b'\r'),
+            't' => out.push(b'\t'),
+            'v' => out.push(0x0B),
+            'c' => return false,
+            '0' => {
+                let value = take_digits(&mut chars, 8, 3).unwrap_or(0);
+                out.push(value as u8);
+            }
+            'x' => match take_digits(&mut chars, 16, 2) {
+                Some(value) => out.push(value as u8),
+                None => out.extend_from_slice(b"\\x"),
+            },
+            'u' | 'U' => {
+                let max = if next == 'u' { 4 } else { 8 };
+                let digits = chars.clone();
+                match take_digits(&mut chars, 16, max).and_then(char::from_u32) {
+                    Some(ch) => {
+                        let mut buf = [0; 4];
+                        out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
                     }
-                    if let Some(l) = lo {
-                        handle.write_all(&[l as u8]).unwrap();
+                    None => {
+                        // Not a code point, e.g. a surrogate: keep the escape
+                        // as written, digits included.
+                        out.push(b'\\');
+                        out.push(next as u8);
+                        let digits: String =
+                            digits.take(max).take_while(char::is_ascii_hexdigit).collect();
+                        out.extend_from_slice(digits.as_bytes());
                     }
                 }
-                Some('n') => {
-                    chars.next();
-                    handle.write_all(b"\n").unwrap();
-                }
-                Some('t') => {
-                    chars.next();
-                    handle.write_all(b"\t").unwrap();
-                }
-                Some('r') => {
-                    chars.next();
-                    handle.write_all(b"\r").unwrap();
-                }
-                Some('c') => return, 
-                Some('\\') => {
-                    chars.next();
-                    handle.write_all(b"\\").unwrap();
-                }
-                Some(other) => {
-                    handle.write_all(&[b'\\', *other as u8]).unwrap();
-                }
-                None => {
-                    handle.write_all(b"\\").unwrap();
-                }
             }
-        } else {
-            handle.write_all(&[c as u8]).unwrap();
+            other => {
+                out.push(b'\\');
+                let mut buf = [0; 4];
+                out.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
         }
     }
+    true
+}
+
+/// Consumes up to `max` digits in `radix`, returning their value if any.
+fn take_digits(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    radix: u32,
+    max: usize,
+) -> Option<u32> {
+    let mut value: Option<u32> = None;
+    for _ in 0..max {
+        match chars.peek().and_then(|c| c.to_digit(radix)) {
+            Some(d) => {
+                value = Some(value.unwrap_or(0) * radix + d);
+                chars.next();
+            }
+            None => break,
+        }
+    }
+    value
+}
 
-    handle.write_all(b"\n").unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unescaped(s: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        unescape(s, &mut out);
+        out
+    }
+
+    #[test]
+    fn expands_unicode_escapes() {
+        assert_eq!(unescaped("\\u00e9\\U0001F600"), "é😀".as_bytes());
+    }
+
+    #[test]
+    fn keeps_invalid_code_points_as_written() {
+        assert_eq!(unescaped("a\\uD800zz"), b"a\\uD800zz");
+        assert_eq!(unescaped("\\U00110000!"), b"\\U00110000!");
+        assert_eq!(unescaped("\\uzz"), b"\\uzz");
+    }
 }
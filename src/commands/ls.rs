@@ -1,8 +1,15 @@
-use chrono::{DateTime, Utc};
+use super::options::{Arg, Parser};
+use super::strerror;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, SecondsFormat, Utc};
 use chrono_tz::Tz;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::IsTerminal;
 use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{
-    fs, io,
+    env, fs, io,
     path::{Path, PathBuf},
 };
 use terminal_size::{terminal_size, Width};
@@ -15,9 +22,27 @@ struct FileInfo {
     user: String,
     group: String,
     size_or_device: SizeOrDevice,
-    modified_time: String,
+    /// The selected timestamp, formatted for long format.
+    time: String,
     name: String,
     path: PathBuf,
+    /// The bare file name, used for sorting.
+    sort_name: String,
+    /// Where a symlink points.
+    target: Option<PathBuf>,
+    size: u64,
+    blocks: u64,
+    inode: u64,
+    /// The SELinux security context, or `?` if there is none (`-Z`).
+    context: String,
+    /// Extended attribute names and value sizes (`--xattr`).
+    xattrs: Vec<(String, usize)>,
+    /// The selected timestamp, used by `-t`.
+    timestamp: SystemTime,
+    /// The modification time, for machine-readable formats.
+    modified: SystemTime,
+    /// Whether this is a directory or a symlink to one.
+    is_dir: bool,
 }
 
 enum SizeOrDevice {
@@ -25,287 +50,955 @@ enum SizeOrDevice {
     Device { major: u64, minor: u64 },
 }
 
-pub fn ls(args: Vec<String>) -> io::Result<()> {
-    let (directories, show_hidden, long_format, classify) = match filter_flags(args.clone()) {
-        Some(result) => result,
-        None => {
-            println!("ls: invalid flag");
-            return Ok(());
+/// Options selected by `ls`'s flags.
+#[derive(Default)]
+pub struct LsOptions {
+    paths: Vec<String>,
+    dot_files: DotFiles,
+    /// Skip names ending in `~` (`-B`).
+    ignore_backups: bool,
+    /// Globs of names never listed (`-I`).
+    ignore: Vec<String>,
+    /// Globs of names not listed unless `-a` or `-A` is given.
+    hide: Vec<String>,
+    format: Format,
+    classify: bool,
+    /// List directory operands themselves rather than their contents (`-d`).
+    directory: bool,
+    show_inode: bool,
+    /// Print each entry's security context (`-Z`).
+    show_context: bool,
+    /// List extended attributes under each entry in long format (`-@`).
+    show_xattrs: bool,
+    /// Show user and group IDs instead of names (`-n`).
+    numeric_ids: bool,
+    /// Omit the owner column (`-g`).
+    hide_owner: bool,
+    /// Omit the group column (`-o`).
+    hide_group: bool,
+    sort: SortKey,
+    reverse: bool,
+    group_directories_first: bool,
+    recursive: bool,
+    /// Print each entry's allocated size (`-s`).
+    show_blocks: bool,
+    /// Unit for sizes and block counts; `None` shows sizes in bytes and
+    /// blocks in kibibytes.
+    size_unit: Option<SizeUnit>,
+    /// Name colors, when `--color` is in effect.
+    colors: Option<Colors>,
+    time_field: TimeField,
+    time_style: TimeStyle,
+    /// Time zone for timestamps, from `TZ` or the system setting.
+    tz: Tz,
+}
+
+/// Which entries starting with `.` are listed.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum DotFiles {
+    #[default]
+    Hidden,
+    /// All but `.` and `..` (`-A`).
+    AlmostAll,
+    /// All, including `.` and `..` (`-a`).
+    All,
+}
+
+/// How entries are laid out.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum Format {
+    /// Names in columns, filled top to bottom (`-C`).
+    #[default]
+    Columns,
+    /// Names in columns, filled left to right (`-x`).
+    Across,
+    /// One name per line (`-1`).
+    SingleColumn,
+    /// Names separated by commas (`-m`).
+    Commas,
+    /// Details of one entry per line (`-l`).
+    Long,
+    /// A JSON array of entry details (`--format=json`).
+    Json,
+    /// Entry details as CSV with a header row (`--format=csv`).
+    Csv,
+}
+
+/// Which timestamp is shown and sorted by.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum TimeField {
+    #[default]
+    Modified,
+    /// Last access (`-u`).
+    Accessed,
+    /// Last status change (`-c`).
+    Changed,
+    /// Creation (`--time=birth`).
+    Birth,
+}
+
+/// `strftime` formats for timestamps older and newer than six months.
+struct TimeStyle {
+    old: String,
+    recent: String,
+}
+
+/// How sizes and block counts are shown.
+#[derive(Clone)]
+enum SizeUnit {
+    /// Scaled to a readable number in steps of `base` (`-h`, `--si`).
+    Human { base: u64 },
+    /// Counted in `bytes`-sized units, rounded up, followed by `suffix`.
+    Fixed { bytes: u64, suffix: String },
+}
+
+/// What entries are ordered by.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum SortKey {
+    #[default]
+    Name,
+    /// Newest first (`-t`).
+    Time,
+    /// Largest first (`-S`).
+    Size,
+    /// By extension, then name (`-X`).
+    Extension,
+    /// Natural order of numbers within names (`-v`).
+    Version,
+    /// Directory order (`-U`).
+    None,
+}
+
+/// Colors for file names, from `LS_COLORS` on top of built-in defaults.
+struct Colors {
+    /// SGR codes by file type key (`di`, `ln`, `ex`...).
+    types: HashMap<String, String>,
+    /// SGR codes for names ending in a suffix (`*.tar=01;31`).
+    suffixes: Vec<(String, String)>,
+}
+
+/// Colors used for keys `LS_COLORS` does not set, as in `dircolors`.
+const DEFAULT_COLORS: &str = "di=01;34:ln=01;36:pi=40;33:so=01;35:bd=40;33;01:cd=40;33;01:\
+or=40;31;01:su=37;41:sg=30;43:tw=30;42:ow=34;42:st=37;44:ex=01;32";
+
+/// How names are compared, chosen from the locale.
+#[derive(Clone, Copy)]
+enum Collation {
+    /// Byte order, for the `C` and `POSIX` locales.
+    Bytes,
+    /// Case and punctuation are ignored first, like most UTF-8 locales.
+    Dictionary,
+}
+
+/// Lists directory contents.
+///
+/// # Options
+///
+/// - `-a`: include entries starting with `.`, plus `.` and `..`; `-A`:
+///   the same without `.` and `..`.
+/// - `-B`: skip names ending in `~`; `-I PATTERN`, `--ignore=PATTERN`: skip
+///   names matching a glob; `--hide=PATTERN`: the same unless `-a` or `-A`
+///   is given.
+/// - `-l`: long format; `-g` and `-o` omit the owner or group, `-n` shows
+///   numeric IDs.
+/// - `-C`, `-x`, `-1`, `-m`: names in columns down or across, one per line,
+///   or separated by commas.
+/// - `--format=WORD`: any of the above by name, or `json` or `csv` for one
+///   record per entry with its name, path, type, mode, links, owner, group,
+///   size or device numbers, ISO-8601 mtime and symlink target.
+/// - `-F`: append a type indicator to names; `-i`: print inode numbers.
+/// - `-d`: list directories themselves, not their contents.
+/// - `-t`, `-S`, `-X`, `-v`, `-U`: sort by time, size, extension, version
+///   or not at all instead of by name; `--sort=WORD` does the same.
+/// - `-r`: reverse the order; `--group-directories-first`: list
+///   directories before other files.
+/// - `-R`: list subdirectories recursively, each under a `dir:` header.
+/// - `-s`: print each entry's allocated size.
+/// - `-Z`: print each entry's SELinux security context; `-@`, `--xattr`:
+///   in long format, list extended attribute names and sizes under each
+///   entry.
+/// - `-h`, `--si`: show sizes as `1.5K`, `23M`... in powers of 1024 or
+///   1000; `--block-size=SIZE` counts sizes in units of `SIZE` instead.
+/// - `-u`, `-c`, `--time=WORD`: show and sort by access, status change or
+///   birth time instead of modification time.
+/// - `--time-style=STYLE`: `iso`, `long-iso`, `full-iso` or `+FORMAT`;
+///   `--full-time` is `-l --time-style=full-iso`. Times are shown in the
+///   `TZ` time zone.
+/// - `--color[=WHEN]`: color names by type per `LS_COLORS`, `always`,
+///   `never` or `auto` (only when stdout is a terminal).
+/// - `--`: treat every following argument as a file, e.g. `ls -- -foo`.
+///
+/// # Returns
+///
+/// `0` on success, `1` if a directory could not be read, and `2` if a
+/// file operand does not exist.
+///
+/// # Behavior
+///
+/// - File operands are listed first, then each directory operand, all in
+///   the selected order.
+/// - Names are collated per `LC_ALL`, `LC_COLLATE` or `LANG`: byte order for
+///   `C`/`POSIX`, otherwise ignoring case and punctuation.
+/// - In long format the mode is followed by `+` for entries with a POSIX
+///   ACL and `.` for entries with only a security context.
+pub fn ls(args: Vec<String>) -> i32 {
+    let opts = match parse_args(args) {
+        Ok(opts) => opts,
+        Err(status) => return status,
+    };
+    let mut operands = opts.paths.clone();
+    if operands.is_empty() {
+        operands.push(".".to_string());
+    }
+    let show_headers = operands.len() > 1;
+
+    let mut status = 0;
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for operand in &operands {
+        let path = Path::new(operand);
+        // Symlinks given on the command line are followed unless their own
+        // details are asked for.
+        let meta = if opts.long() || opts.machine_readable() || opts.classify || opts.directory {
+            fs::symlink_metadata(path)
+        } else {
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))
+        };
+        let info = meta.and_then(|meta| {
+            let is_dir = meta.is_dir() && !opts.directory;
+            Ok((is_dir, get_file_info(path, operand, &opts)?))
+        });
+        let (is_dir, info) = match info {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("ls: cannot access '{}': {}", operand, strerror(&e));
+                status = 2;
+                continue;
+            }
+        };
+        if is_dir {
+            dirs.push(info);
+        } else {
+            files.push(info);
         }
+    }
+
+    match opts.format {
+        Format::Json => println!("["),
+        Format::Csv => println!("{}", RECORD_FIELDS.join(",")),
+        _ => {}
+    }
+    let mut printed = false;
+    if !files.is_empty() {
+        sort_infos(&mut files, &opts);
+        print_section(&render(files, &opts, false), &opts, &mut printed);
+    }
+    sort_infos(&mut dirs, &opts);
+    let show_headers = show_headers || opts.recursive;
+    for dir in dirs {
+        let listed = list_tree(
+            &dir.path,
+            &opts,
+            show_headers,
+            &mut Vec::new(),
+            &mut printed,
+        );
+        if !listed {
+            status = status.max(1);
+        }
+    }
+    if opts.format == Format::Json {
+        if printed {
+            println!();
+        }
+        println!("]");
+    }
+    status
+}
+
+/// Prints one section of output, separated from the previous one by a
+/// blank line. JSON and CSV sections continue the same array or table.
+fn print_section(section: &str, opts: &LsOptions, printed: &mut bool) {
+    if section.is_empty() {
+        return;
+    }
+    match opts.format {
+        // The closing `]` goes on its own line after the last record.
+        Format::Json if *printed => print!(",\n{}", section),
+        Format::Json => print!("{}", section),
+        Format::Csv => println!("{}", section),
+        _ if *printed => println!("\n{}", section),
+        _ => println!("{}", section),
+    }
+    *printed = true;
+}
+
+/// Lists `dir` and, with `-R`, every directory below it, depth-first.
+///
+/// `ancestors` holds the device and inode of the directories currently
+/// being listed, so a directory reachable from itself is reported instead
+/// of listed forever.
+///
+/// Returns `false` if any directory could not be listed.
+fn list_tree(
+    dir: &Path,
+    opts: &LsOptions,
+    show_header: bool,
+    ancestors: &mut Vec<(u64, u64)>,
+    printed: &mut bool,
+) -> bool {
+    let id = fs::metadata(dir).map(|m| (m.dev(), m.ino())).ok();
+    if let Some(id) = id {
+        if ancestors.contains(&id) {
+            eprintln!(
+                "ls: {}: not listing already-listed directory",
+                dir.display()
+            );
+            return false;
+        }
+    }
+
+    let (listing, subdirs) = match list_dir(dir, opts) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!(
+                "ls: cannot open directory '{}': {}",
+                dir.display(),
+                strerror(&e)
+            );
+            return false;
+        }
+    };
+    let section = match (show_header && !opts.machine_readable(), listing.is_empty()) {
+        (false, _) => listing,
+        (true, true) => format!("{}:", dir.display()),
+        (true, false) => format!("{}:\n{}", dir.display(), listing),
     };
-    let mut eh = false;
-    let mut effective_dirs = directories.clone();
-    if effective_dirs.is_empty() {
-        effective_dirs.push(".".to_string());
-    }
-
-    let mut output_sections = Vec::new();
-    let mut error_messages = Vec::new();
-    let mut files_out = Vec::new();
-let mut files=Vec::new();
-    for (i, dir_path_str) in effective_dirs.iter().enumerate() {
-        if effective_dirs.len() > 1 {
-            if i > 0 {
-                output_sections.push(String::new());
+    print_section(&section, opts, printed);
+
+    if !opts.recursive {
+        return true;
+    }
+    let mut ok = true;
+    ancestors.extend(id);
+    for subdir in subdirs {
+        ok &= list_tree(&subdir, opts, true, ancestors, printed);
+    }
+    if id.is_some() {
+        ancestors.pop();
+    }
+    ok
+}
+
+/// Lists the entries of one directory in the selected order and format.
+///
+/// Also returns the subdirectories to descend into with `-R`, in the same
+/// order; symlinks and `.`/`..` are never descended into.
+fn list_dir(dir: &Path, opts: &LsOptions) -> io::Result<(String, Vec<PathBuf>)> {
+    let entries = fs::read_dir(dir)?;
+    let mut infos = Vec::new();
+    if opts.dot_files == DotFiles::All {
+        for (path, name) in [(dir.to_path_buf(), "."), (dir.join(".."), "..")] {
+            if !is_listed(name, opts) {
+                continue;
             }
+            if let Ok(info) = get_file_info(&path, name, opts) {
+                infos.push(info);
+            }
+        }
+    }
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !is_listed(&name, opts) {
+            continue;
+        }
+        if let Ok(info) = get_file_info(&entry.path(), &name, opts) {
+            infos.push(info);
         }
+    }
+    sort_infos(&mut infos, opts);
 
-        let dir_path = Path::new(dir_path_str);
-        match fs::read_dir(dir_path) {
-            Ok(entries) => {
-                let mut file_infos = Vec::new();
-                if effective_dirs.len() != 1 {
-                    if eh {
-                        output_sections.push(format!("\n{}:", dir_path_str));
-                    } else {
-                        output_sections.push(format!("{}:", dir_path_str));
-                        eh = true
-                    }
-                }
+    let subdirs = if opts.recursive {
+        infos
+            .iter()
+            .filter(|info| info.sort_name != "." && info.sort_name != "..")
+            .filter(|info| fs::symlink_metadata(&info.path).is_ok_and(|m| m.is_dir()))
+            .map(|info| info.path.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok((render(infos, opts, true), subdirs))
+}
 
-                if show_hidden {
-                    if let Ok(info) =
-                        get_file_info(dir_path, classify, long_format, Some(dir_path_str))
-                    {
-                        file_infos.push(info);
-                    }
-                    if let Ok(info) = get_file_info(
-                        &dir_path.join(".."),
-                        classify,
-                        long_format,
-                        Some(dir_path_str),
-                    ) {
-                        file_infos.push(info);
-                    }
-                }
+/// Whether a directory entry is listed, per `-a`, `-A`, `-B`, `--ignore`
+/// and `--hide`.
+fn is_listed(name: &str, opts: &LsOptions) -> bool {
+    let hidden = opts.dot_files == DotFiles::Hidden;
+    if hidden && name.starts_with('.') {
+        return false;
+    }
+    if opts.ignore_backups && name.ends_with('~') {
+        return false;
+    }
+    if opts.ignore.iter().any(|p| glob_match(p, name)) {
+        return false;
+    }
+    !(hidden && opts.hide.iter().any(|p| glob_match(p, name)))
+}
 
-                let mut paths: Vec<PathBuf> =
-                    entries.filter_map(Result::ok).map(|e| e.path()).collect();
-
-                paths.sort_by(|a, b| {
-                    let a_name = a
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .map(|s| {
-                            s.chars()
-                                .filter(|c| c.is_alphanumeric())
-                                .collect::<String>()
-                                .to_lowercase()
-                        })
-                        .unwrap_or_default();
-                    let b_name = b
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .map(|s| {
-                            s.chars()
-                                .filter(|c| c.is_alphanumeric())
-                                .collect::<String>()
-                                .to_lowercase()
-                        })
-                        .unwrap_or_default();
-
-                    a_name.cmp(&b_name).then_with(|| {
-                        a.file_name()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or_default()
-                            .to_lowercase()
-                            .cmp(
-                                &b.file_name()
-                                    .and_then(|s| s.to_str())
-                                    .unwrap_or_default()
-                                    .to_lowercase(),
-                            )
-                    })
-                });
-
-                for path in &paths {
-                    if let Ok(info) = get_file_info(path, classify, long_format, Some(dir_path_str))
-                    {
-                        if !show_hidden && info.name.starts_with('.') {
-                            continue;
-                        }
-                        file_infos.push(info);
-                    }
-                }
+/// Matches `name` against a shell glob with `*`, `?`, `[...]` and `\`
+/// escapes. Like `fnmatch` with `FNM_PERIOD`, a leading `.` in `name` is
+/// only matched by a literal `.`.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // Where to resume after the last `*` when the rest fails to match.
+    let mut backtrack = None;
+    while ni < n.len() {
+        let step = match p.get(pi) {
+            Some('*') => {
+                pi += 1;
+                backtrack = Some((pi, ni));
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match match_class(&p[pi + 1..], n[ni]) {
+                Some((matched, len)) => matched.then_some(len + 1),
+                // An unterminated class is a literal `[`.
+                None => (n[ni] == '[').then_some(1),
+            },
+            Some('\\') if pi + 1 < p.len() => (p[pi + 1] == n[ni]).then_some(2),
+            Some(&c) => (c == n[ni]).then_some(1),
+            None => None,
+        };
+        match (step, backtrack) {
+            (Some(len), _) => {
+                pi += len;
+                ni += 1;
+            }
+            (None, Some((star_pi, star_ni))) => {
+                pi = star_pi;
+                ni = star_ni + 1;
+                backtrack = Some((star_pi, ni));
+            }
+            (None, None) => return false,
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
 
-                file_infos.sort_by(|a, b| {
-                    let a_is_dot = a.name == "." || a.name == "./";
-                    let b_is_dot = b.name == "." || b.name == "./";
-                    let a_is_dotdot = a.name == ".." || a.name == "../";
-                    let b_is_dotdot = b.name == ".." || b.name == "../";
+/// Matches `c` against the bracket expression following a `[`.
+///
+/// Returns whether it matched and the length of the expression including
+/// the closing `]`, or `None` if it is not terminated.
+fn match_class(class: &[char], c: char) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!' | '^'));
+    let mut i = usize::from(negated);
+    let mut matched = false;
+    let start = i;
+    loop {
+        let lo = *class.get(i)?;
+        // A `]` right after the `[` (or `[!`) is part of the class.
+        if lo == ']' && i > start {
+            return Some((matched != negated, i + 1));
+        }
+        match (class.get(i + 1), class.get(i + 2)) {
+            (Some('-'), Some(&hi)) if hi != ']' => {
+                matched |= lo <= c && c <= hi;
+                i += 3;
+            }
+            _ => {
+                matched |= lo == c;
+                i += 1;
+            }
+        }
+    }
+}
 
-                    if a_is_dot {
-                        return std::cmp::Ordering::Less;
-                    }
-                    if b_is_dot {
-                        return std::cmp::Ordering::Greater;
-                    }
-                    if a_is_dotdot {
-                        return if b_is_dot {
-                            std::cmp::Ordering::Greater
-                        } else {
-                            std::cmp::Ordering::Less
-                        };
-                    }
-                    if b_is_dotdot {
-                        return if a_is_dot {
-                            std::cmp::Ordering::Less
-                        } else {
-                            std::cmp::Ordering::Greater
-                        };
-                    }
+/// Formats entries in columns or in long format, the latter optionally
+/// preceded by the `total` line of a directory listing.
+fn render(infos: Vec<FileInfo>, opts: &LsOptions, with_total: bool) -> String {
+    match opts.format {
+        Format::Json => {
+            let records: Vec<String> = infos.iter().map(|i| json_record(i, opts.tz)).collect();
+            return records.join(",\n");
+        }
+        Format::Csv => {
+            let records: Vec<String> = infos.iter().map(|i| csv_record(i, opts.tz)).collect();
+            return records.join("\n");
+        }
+        _ => {}
+    }
+    let total = opts.format_blocks(infos.iter().map(|info| info.blocks).sum());
+    if opts.long() {
+        let listing = format_long_columns(infos, opts);
+        return match (with_total, listing.is_empty()) {
+            (false, _) => listing,
+            (true, true) => format!("total {}", total),
+            (true, false) => format!("total {}\n{}", total, listing),
+        };
+    }
 
-                    let a_name_sort = a
-                        .path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .map(|s| {
-                            s.chars()
-                                .filter(|c| c.is_alphanumeric())
-                                .collect::<String>()
-                                .to_lowercase()
-                        })
-                        .unwrap_or_default();
-                    let b_name_sort = b
-                        .path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .map(|s| {
-                            s.chars()
-                                .filter(|c| c.is_alphanumeric())
-                                .collect::<String>()
-                                .to_lowercase()
-                        })
-                        .unwrap_or_default();
-
-                    a_name_sort.cmp(&b_name_sort)
-                });
-
-                if long_format {
-                    let total_blocks: u64 = file_infos
-                        .iter()
-                        .filter_map(|info| fs::symlink_metadata(&info.path).ok())
-                        .map(|m| m.blocks())
-                        .sum();
-
-                    output_sections.push(format!("total {}", total_blocks / 2));
-                    output_sections.push(format_long_columns(file_infos));
-                } else {
-                    let names = file_infos.into_iter().map(|fi| fi.name).collect();
-                    output_sections.push(format_columns(names));
-                }
+    let items = prefixes(&infos, opts)
+        .into_iter()
+        .zip(infos)
+        .map(|(prefix, info)| prefix + &info.name)
+        .collect();
+    let listing = match opts.format {
+        Format::Across => format_columns(items, true),
+        Format::SingleColumn => items.join("\n"),
+        Format::Commas => format_commas(items),
+        Format::Columns | Format::Long | Format::Json | Format::Csv => {
+            format_columns(items, false)
+        }
+    };
+    if !with_total || !opts.show_blocks {
+        return listing;
+    }
+    if listing.is_empty() {
+        format!("total {}", total)
+    } else {
+        format!("total {}\n{}", total, listing)
+    }
+}
+
+/// Returns the `-i` and `-s` columns that precede each entry, each
+/// right-aligned and followed by a space. Outside long format these also
+/// include the `-Z` column.
+fn prefixes(infos: &[FileInfo], opts: &LsOptions) -> Vec<String> {
+    let mut columns: Vec<Vec<String>> = Vec::new();
+    if opts.show_inode {
+        columns.push(infos.iter().map(|i| i.inode.to_string()).collect());
+    }
+    if opts.show_blocks {
+        columns.push(infos.iter().map(|i| opts.format_blocks(i.blocks)).collect());
+    }
+    if opts.show_context && !opts.long() {
+        columns.push(infos.iter().map(|i| i.context.clone()).collect());
+    }
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|c| c.iter().map(String::len).max().unwrap_or(0))
+        .collect();
+    (0..infos.len())
+        .map(|row| {
+            columns
+                .iter()
+                .zip(&widths)
+                .map(|(column, width)| format!("{:>width$} ", column[row]))
+                .collect()
+        })
+        .collect()
+}
+
+/// Orders entries by the selected key, then applies `-r` and
+/// `--group-directories-first`.
+fn sort_infos(infos: &mut [FileInfo], opts: &LsOptions) {
+    if opts.sort != SortKey::None {
+        let collation = Collation::from_env();
+        infos.sort_by(|a, b| {
+            let ord = compare(a, b, opts.sort, collation);
+            if opts.reverse {
+                ord.reverse()
+            } else {
+                ord
             }
-            Err(e) => {
-                if e.kind().to_string() == "not a directory" {
-                    files.push(dir_path);
-                } else {
-                    error_messages.push(format!(
-                        "ls: cannot access '{}': {}",
-                        dir_path_str,
-                        e.kind()
-                    ));
-                }
+        });
+    }
+    if opts.group_directories_first {
+        // A stable sort keeps the order within each group.
+        infos.sort_by_key(|info| !info.is_dir);
+    }
+}
+
+fn compare(a: &FileInfo, b: &FileInfo, key: SortKey, collation: Collation) -> Ordering {
+    let by_name = || collation.compare(&a.sort_name, &b.sort_name);
+    match key {
+        SortKey::Name | SortKey::None => by_name(),
+        SortKey::Time => b.timestamp.cmp(&a.timestamp).then_with(by_name),
+        SortKey::Size => b.size.cmp(&a.size).then_with(by_name),
+        SortKey::Extension => collation
+            .compare(extension(&a.sort_name), extension(&b.sort_name))
+            .then_with(by_name),
+        SortKey::Version => version_cmp(&a.sort_name, &b.sort_name),
+    }
+}
+
+impl TimeField {
+    fn of(self, meta: &fs::Metadata) -> Option<SystemTime> {
+        match self {
+            TimeField::Modified => meta.modified().ok(),
+            TimeField::Accessed => meta.accessed().ok(),
+            TimeField::Birth => meta.created().ok(),
+            TimeField::Changed => {
+                let since_epoch = Duration::new(meta.ctime() as u64, meta.ctime_nsec() as u32);
+                UNIX_EPOCH.checked_add(since_epoch)
             }
         }
     }
-    files.sort_by(|a, b| {
-        let a_name = a
-            .file_name()
-            .and_then(|s| s.to_str())
-            .map(|s| {
-                s.chars()
-                    .filter(|c| c.is_alphanumeric())
-                    .collect::<String>()
-                    .to_lowercase()
-            })
-            .unwrap_or_default();
-        let b_name = b
-            .file_name()
-            .and_then(|s| s.to_str())
-            .map(|s| {
-                s.chars()
-                    .filter(|c| c.is_alphanumeric())
-                    .collect::<String>()
-                    .to_lowercase()
-            })
-            .unwrap_or_default();
-
-        a_name.cmp(&b_name).then_with(|| {
-            a.file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or_default()
-                .to_lowercase()
-                .cmp(
-                    &b.file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or_default()
-                        .to_lowercase(),
-                )
+}
+
+impl Default for TimeStyle {
+    fn default() -> TimeStyle {
+        TimeStyle {
+            old: "%b %e  %Y".to_string(),
+            recent: "%b %e %H:%M".to_string(),
+        }
+    }
+}
+
+impl TimeStyle {
+    /// Parses a `--time-style` argument: `full-iso`, `long-iso`, `iso`,
+    /// `locale`, or `+FORMAT` with an optional second format for recent
+    /// files after a newline. A `posix-` prefix is ignored.
+    fn parse(style: &str) -> Option<TimeStyle> {
+        let style = style.strip_prefix("posix-").unwrap_or(style);
+        let (old, recent) = match style {
+            "full-iso" => ("%Y-%m-%d %H:%M:%S%.9f %z", "%Y-%m-%d %H:%M:%S%.9f %z"),
+            "long-iso" => ("%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M"),
+            "iso" => ("%Y-%m-%d ", "%m-%d %H:%M"),
+            "locale" => return Some(TimeStyle::default()),
+            _ => {
+                let format = style.strip_prefix('+')?;
+                format.split_once('\n').unwrap_or((format, format))
+            }
+        };
+        let valid = |f: &str| !StrftimeItems::new(f).any(|item| matches!(item, Item::Error));
+        if !valid(old) || !valid(recent) {
+            return None;
+        }
+        Some(TimeStyle {
+            old: old.to_string(),
+            recent: recent.to_string(),
         })
-    });
-for fi in files {
-    let mut file_infos = Vec::new();
-                    if let Ok(info) = get_file_info(fi, classify, long_format, None) {
-                        file_infos.push(info);
-                    }
-                    if long_format {
-                        files_out.push(format_long_columns(file_infos));
-                    } else {
-                        let names = file_infos.into_iter().map(|fi| fi.name).collect();
-                        files_out.push(format_columns(names));
-                    }
+    }
+
+    fn format(&self, time: SystemTime, tz: Tz) -> String {
+        let utc: DateTime<Utc> = time.into();
+        let age = Utc::now().signed_duration_since(utc);
+        let recent = age < chrono::Duration::days(30 * 6) && age.num_seconds() >= 0;
+        let format = if recent { &self.recent } else { &self.old };
+        utc.with_timezone(&tz).format(format).to_string()
+    }
+}
+
+/// Returns the time zone named by `TZ`, or else the system's.
+fn local_tz() -> Tz {
+    let name = match env::var("TZ") {
+        Ok(tz) if !tz.is_empty() => tz.trim_start_matches(':').to_string(),
+        _ => iana_time_zone::get_timezone().unwrap_or_else(|_| "UTC".to_string()),
+    };
+    name.parse().unwrap_or(Tz::UTC)
 }
-    if !error_messages.is_empty() {
-        println!("{}", error_messages.join("\n"));
+
+impl LsOptions {
+    fn long(&self) -> bool {
+        self.format == Format::Long
     }
-    if !files_out.is_empty() {
-        println!(
-            "{}",
-            if long_format {
-                files_out.join("\n")
-            } else {
-                files_out.join(" ")
+
+    /// Whether entries are printed as JSON or CSV records rather than text.
+    fn machine_readable(&self) -> bool {
+        matches!(self.format, Format::Json | Format::Csv)
+    }
+
+    /// Formats a file size for the size column.
+    fn format_size(&self, bytes: u64) -> String {
+        match &self.size_unit {
+            Some(unit) => unit.format(bytes),
+            None => bytes.to_string(),
+        }
+    }
+
+    /// Formats a number of 512-byte blocks for `-s` and the `total` line.
+    fn format_blocks(&self, blocks: u64) -> String {
+        let bytes = blocks * 512;
+        match &self.size_unit {
+            Some(unit) => unit.format(bytes),
+            None => bytes.div_ceil(1024).to_string(),
+        }
+    }
+}
+
+impl SizeUnit {
+    /// Parses a `--block-size` argument: `human-readable`, `si`, or an
+    /// optional count followed by a unit such as `K`, `MiB` or `kB`.
+    fn parse(spec: &str) -> Option<SizeUnit> {
+        match spec {
+            "human-readable" => return Some(SizeUnit::Human { base: 1024 }),
+            "si" => return Some(SizeUnit::Human { base: 1000 }),
+            _ => {}
+        }
+        let digits = spec
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(spec.len());
+        let (count, suffix) = spec.split_at(digits);
+        let count: u64 = if count.is_empty() {
+            1
+        } else {
+            count.parse().ok().filter(|&n| n > 0)?
+        };
+        let (base, power) = match suffix.as_bytes() {
+            [] => (1, 0),
+            [p] | [p, b'i', b'B'] => (1024, power_of(*p)?),
+            [p, b'B'] => (1000, power_of(*p)?),
+            _ => return None,
+        };
+        let bytes = count.checked_mul(u64::checked_pow(base, power)?)?;
+        // Like GNU, a unit is only printed when no count was given.
+        let suffix = if digits == 0 {
+            suffix.to_string()
+        } else {
+            String::new()
+        };
+        Some(SizeUnit::Fixed { bytes, suffix })
+    }
+
+    fn format(&self, bytes: u64) -> String {
+        match self {
+            SizeUnit::Human { base } => human_size(bytes, *base),
+            SizeUnit::Fixed {
+                bytes: unit,
+                suffix,
+            } => {
+                format!("{}{}", bytes.div_ceil(*unit), suffix)
             }
-        );
+        }
     }
-    if !output_sections.iter().filter(|a| !a.is_empty()).collect::<Vec<&String>>().is_empty() && !files_out.is_empty() {
-        println!();
+}
+
+/// Returns the power of 1024 or 1000 a unit letter stands for.
+fn power_of(unit: u8) -> Option<u32> {
+    b"KMGTPE"
+        .iter()
+        .position(|&p| p == unit.to_ascii_uppercase())
+        .map(|i| i as u32 + 1)
+}
+
+/// Formats `bytes` like `ls -h`: one decimal below 10, rounded up, with a
+/// `K`, `M`, `G`... suffix (`k` for kilo with `--si`).
+fn human_size(bytes: u64, base: u64) -> String {
+    const PREFIXES: &[u8] = b"KMGTPE";
+    if bytes < base {
+        return bytes.to_string();
     }
-    for (_, section) in output_sections.iter().filter(|a| !a.is_empty()).enumerate() {
-        println!("{}", section);
+    let base_f = base as f64;
+    let mut value = bytes as f64 / base_f;
+    let mut power = 0;
+    loop {
+        let rounded = if value < 10.0 {
+            (value * 10.0).ceil() / 10.0
+        } else {
+            value.ceil()
+        };
+        if rounded >= base_f && power + 1 < PREFIXES.len() {
+            value /= base_f;
+            power += 1;
+            continue;
+        }
+        let prefix = match (base, power) {
+            (1000, 0) => 'k',
+            _ => PREFIXES[power] as char,
+        };
+        return if rounded < 10.0 {
+            format!("{:.1}{}", rounded, prefix)
+        } else {
+            format!("{}{}", rounded, prefix)
+        };
     }
-    Ok(())
 }
 
-fn get_file_info(
-    path: &Path,
-    classify: bool,
-    long_format: bool,
-    original_dir: Option<&str>,
-) -> io::Result<FileInfo> {
+impl Colors {
+    fn from_env() -> Colors {
+        let mut colors = Colors {
+            types: HashMap::new(),
+            suffixes: Vec::new(),
+        };
+        colors.parse(DEFAULT_COLORS);
+        if let Ok(spec) = env::var("LS_COLORS") {
+            colors.parse(&spec);
+        }
+        colors
+    }
+
+    /// Adds `key=code` pairs separated by `:`, replacing earlier ones.
+    fn parse(&mut self, spec: &str) {
+        for entry in spec.split(':') {
+            let Some((key, code)) = entry.split_once('=') else {
+                continue;
+            };
+            match key.strip_prefix('*') {
+                Some(suffix) => {
+                    let suffix = suffix.to_ascii_lowercase();
+                    self.suffixes.retain(|(s, _)| *s != suffix);
+                    self.suffixes.push((suffix, code.to_string()));
+                }
+                None => {
+                    self.types.insert(key.to_string(), code.to_string());
+                }
+            }
+        }
+    }
+
+    /// Returns the code for a type key, unless it is unset or plain (`0`).
+    fn get(&self, key: &str) -> Option<&str> {
+        self.types
+            .get(key)
+            .map(String::as_str)
+            .filter(|code| code.bytes().any(|b| b != b'0'))
+    }
+
+    /// Picks the color of a file the way GNU `ls` does: special mode bits
+    /// first, then the file type, then the name's suffix.
+    fn code(&self, name: &str, meta: &fs::Metadata, path: &Path) -> Option<&str> {
+        let file_type = meta.file_type();
+        let mode = meta.mode();
+        if file_type.is_symlink() {
+            return match fs::metadata(path) {
+                Err(_) => self.get("or").or_else(|| self.get("ln")),
+                Ok(target) if self.types.get("ln").is_some_and(|c| c == "target") => {
+                    self.code(name, &target, path)
+                }
+                Ok(_) => self.get("ln"),
+            };
+        }
+
+        let candidates: &[(&str, bool)] = if file_type.is_dir() {
+            &[
+                ("tw", mode & 0o1002 == 0o1002),
+                ("ow", mode & 0o002 != 0),
+                ("st", mode & 0o1000 != 0),
+                ("di", true),
+            ]
+        } else if file_type.is_fifo() {
+            &[("pi", true)]
+        } else if file_type.is_socket() {
+            &[("so", true)]
+        } else if file_type.is_block_device() {
+            &[("bd", true)]
+        } else if file_type.is_char_device() {
+            &[("cd", true)]
+        } else {
+            &[
+                ("su", mode & 0o4000 != 0),
+                ("sg", mode & 0o2000 != 0),
+                ("ex", mode & 0o111 != 0),
+            ]
+        };
+        let by_type = candidates
+            .iter()
+            .filter(|(_, applies)| *applies)
+            .find_map(|(key, _)| self.get(key));
+        if by_type.is_some() || !file_type.is_file() {
+            return by_type;
+        }
+
+        let name = name.to_ascii_lowercase();
+        self.suffixes
+            .iter()
+            .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .map(|(_, code)| code.as_str())
+            .filter(|code| code.bytes().any(|b| b != b'0'))
+            .or_else(|| self.get("fi"))
+    }
+
+    fn paint(&self, text: &str, code: Option<&str>) -> String {
+        match code {
+            Some(code) => format!("\x1b[{}m{}\x1b[0m", code, text),
+            None => text.to_string(),
+        }
+    }
+}
+
+impl Collation {
+    fn from_env() -> Collation {
+        let locale = ["LC_ALL", "LC_COLLATE", "LANG"]
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()));
+        match locale.as_deref() {
+            Some("C" | "POSIX") => Collation::Bytes,
+            Some(l) if l.starts_with("C.") => Collation::Bytes,
+            _ => Collation::Dictionary,
+        }
+    }
+
+    fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Collation::Bytes => a.cmp(b),
+            Collation::Dictionary => {
+                let key = |s: &str| {
+                    s.chars()
+                        .filter(|c| c.is_alphanumeric())
+                        .collect::<String>()
+                        .to_lowercase()
+                };
+                key(a)
+                    .cmp(&key(b))
+                    .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+                    .then_with(|| a.cmp(b))
+            }
+        }
+    }
+}
+
+/// Returns the extension of `name` including the dot, or `""`.
+fn extension(name: &str) -> &str {
+    name.rfind('.').map_or("", |i| &name[i..])
+}
+
+/// Compares names like `ls -v`: runs of digits compare by numeric value,
+/// everything else byte by byte.
+fn version_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a.as_bytes(), b.as_bytes());
+    while let (Some(&cx), Some(&cy)) = (x.first(), y.first()) {
+        if cx.is_ascii_digit() && cy.is_ascii_digit() {
+            let x_len = x.iter().take_while(|c| c.is_ascii_digit()).count();
+            let y_len = y.iter().take_while(|c| c.is_ascii_digit()).count();
+            let trim = |n: &[u8]| {
+                let zeros = n.iter().take_while(|&&c| c == b'0').count();
+                n[zeros..].to_vec()
+            };
+            let (nx, ny) = (trim(&x[..x_len]), trim(&y[..y_len]));
+            let ord = nx.len().cmp(&ny.len()).then_with(|| nx.cmp(&ny));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            x = &x[x_len..];
+            y = &y[y_len..];
+        } else {
+            if cx != cy {
+                return cx.cmp(&cy);
+            }
+            x = &x[1..];
+            y = &y[1..];
+        }
+    }
+    x.len().cmp(&y.len()).then_with(|| a.cmp(b))
+}
+
+/// Collects the details of `path`, shown as `name`.
+fn get_file_info(path: &Path, name: &str, opts: &LsOptions) -> io::Result<FileInfo> {
     let meta = fs::symlink_metadata(path)?;
     let file_type = meta.file_type();
 
-    let mut name = if Some(path.to_str().unwrap_or_default()) == original_dir {
-        ".".to_string()
-    } else if path.to_str().map_or(false, |s| s.ends_with("/..")) {
-        "..".to_string()
-    } else {
-        path.file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_string()
-    };
-    name = if should_be_in_quotes(&name) {
+    let sort_name = name.to_string();
+    let mut name = if should_be_in_quotes(name) {
         format!("\'{}\'", name)
     } else {
-        name
+        sort_name.clone()
     };
-    if classify {
+    if let Some(colors) = &opts.colors {
+        name = colors.paint(&name, colors.code(&sort_name, &meta, path));
+    }
+    if opts.classify {
         if file_type.is_dir() {
             name.push('/');
         } else if file_type.is_symlink() {
-            if !long_format {
+            if !opts.long() {
                 name.push('@');
             }
         } else {
@@ -313,24 +1006,35 @@ fn get_file_info(
         }
     }
 
-    if long_format && file_type.is_symlink() {
-        if let Ok(target_path) = fs::read_link(path) {
+    let target = match file_type.is_symlink() {
+        true => fs::read_link(path).ok(),
+        false => None,
+    };
+    if opts.long() {
+        if let Some(target_path) = &target {
             let mut target_display = target_path.to_string_lossy().to_string();
-    
-            if classify {
-                if let Some(parent) = path.parent() {
-                    let abs_target = parent.join(&target_path);
-                    if let Ok(target_meta) = fs::metadata(&abs_target) {
-                        let target_type = target_meta.file_type();
-                        target_display.push_str(&classify_suffix(&target_type, &target_meta));
+            let target_meta = fs::metadata(path);
+
+            if let Some(colors) = &opts.colors {
+                let code = match &target_meta {
+                    Ok(target_meta) => {
+                        let target_name = target_path.file_name().unwrap_or_default();
+                        colors.code(&target_name.to_string_lossy(), target_meta, path)
                     }
+                    Err(_) => colors.get("mi").or_else(|| colors.get("or")),
+                };
+                target_display = colors.paint(&target_display, code);
+            }
+            if opts.classify {
+                if let Ok(target_meta) = &target_meta {
+                    let target_type = target_meta.file_type();
+                    target_display.push_str(&classify_suffix(&target_type, target_meta));
                 }
             }
-    
+
             name.push_str(&format!(" -> {}", target_display));
         }
     }
-    
 
     let size_or_device = if file_type.is_block_device() || file_type.is_char_device() {
         SizeOrDevice::Device {
@@ -340,42 +1044,66 @@ fn get_file_info(
     } else {
         SizeOrDevice::Size(meta.len())
     };
-    
-    let tz_name = iana_time_zone::get_timezone().unwrap_or_else(|_| "UTC".to_string());
-    let local_tz: Tz = tz_name.parse().unwrap_or(Tz::UTC);
 
-    let modified_utc: DateTime<Utc> = meta.modified()?.into();
-    let modified_local = modified_utc.with_timezone(&local_tz);
-
-    let duration = Utc::now().signed_duration_since(modified_utc);
-    let six_months = chrono::Duration::days(30 * 6);
-    let is_recent = duration < six_months && duration.num_seconds() >= 0;
+    let timestamp = opts.time_field.of(&meta);
 
     Ok(FileInfo {
         permissions: mode_string(&meta, path),
         links: meta.nlink(),
         user: get_user_by_uid(meta.uid())
+            .filter(|_| !opts.numeric_ids)
             .map(|u| u.name().to_string_lossy().into_owned())
             .unwrap_or_else(|| meta.uid().to_string()),
         group: get_group_by_gid(meta.gid())
+            .filter(|_| !opts.numeric_ids)
             .map(|g| g.name().to_string_lossy().into_owned())
             .unwrap_or_else(|| meta.gid().to_string()),
+        inode: meta.ino(),
+        context: match opts.show_context {
+            true => security_context(path).unwrap_or_else(|| "?".to_string()),
+            false => String::new(),
+        },
+        xattrs: match opts.show_xattrs && opts.long() {
+            true => xattr_sizes(path),
+            false => Vec::new(),
+        },
         size_or_device,
-        modified_time: if is_recent {
-            modified_local.format("%b %e %H:%M").to_string()
-        } else {
-            modified_local.format("%b %e  %Y").to_string()
+        time: match timestamp {
+            Some(timestamp) => opts.time_style.format(timestamp, opts.tz),
+            // Birth times are not available on every file system.
+            None => "-".to_string(),
         },
         name,
         path: path.to_path_buf(),
+        sort_name,
+        target,
+        size: meta.len(),
+        blocks: meta.blocks(),
+        timestamp: timestamp.unwrap_or(UNIX_EPOCH),
+        modified: meta.modified().unwrap_or(UNIX_EPOCH),
+        is_dir: fs::metadata(path).is_ok_and(|m| m.is_dir()),
     })
 }
 
-fn format_long_columns(infos: Vec<FileInfo>) -> String {
+fn format_long_columns(infos: Vec<FileInfo>, opts: &LsOptions) -> String {
     if infos.is_empty() {
         return String::new();
     }
 
+    let prefixes = prefixes(&infos, opts);
+    let max_time_width = infos
+        .iter()
+        .map(|i| i.time.chars().count())
+        .max()
+        .unwrap_or(0);
+    // Only entries with an ACL or security context get a marker after
+    // their mode, so the others are padded when any entry has one.
+    let max_perm_width = infos
+        .iter()
+        .map(|i| i.permissions.len())
+        .max()
+        .unwrap_or(0);
+    let max_context_width = infos.iter().map(|i| i.context.len()).max().unwrap_or(0);
     let mut max_links_width = 0;
     let mut max_user_width = 0;
     let mut max_group_width = 0;
@@ -390,7 +1118,7 @@ fn format_long_columns(infos: Vec<FileInfo>) -> String {
 
         match info.size_or_device {
             SizeOrDevice::Size(size) => {
-                max_size_width = max_size_width.max(size.to_string().len());
+                max_size_width = max_size_width.max(opts.format_size(size).len());
             }
             SizeOrDevice::Device { major, minor } => {
                 max_major_width = max_major_width.max(major.to_string().len());
@@ -399,24 +1127,44 @@ fn format_long_columns(infos: Vec<FileInfo>) -> String {
         }
     }
 
-    let dev_width = max_major_width + 2 + max_minor_width;
+    let dev_width = match max_major_width {
+        0 => 0,
+        _ => max_major_width + 2 + max_minor_width,
+    };
     let size_col_width = max_size_width.max(dev_width);
 
     let mut output = String::new();
-    for info in infos {
+    for (info, prefix) in infos.into_iter().zip(prefixes) {
+        output.push_str(&prefix);
         output.push_str(&format!(
-            "{} {:>links_w$} {:<user_w$} {:<group_w$} ",
+            "{:<perm_w$} {:>links_w$} ",
             info.permissions,
             info.links,
-            info.user,
-            info.group,
+            perm_w = max_perm_width,
             links_w = max_links_width,
-            user_w = max_user_width,
-            group_w = max_group_width
         ));
+        if !opts.hide_owner {
+            output.push_str(&format!("{:<user_w$} ", info.user, user_w = max_user_width));
+        }
+        if !opts.hide_group {
+            output.push_str(&format!(
+                "{:<group_w$} ",
+                info.group,
+                group_w = max_group_width
+            ));
+        }
+        if opts.show_context {
+            output.push_str(&format!(
+                "{:<context_w$} ",
+                info.context,
+                context_w = max_context_width
+            ));
+        }
 
         let size_str = match info.size_or_device {
-            SizeOrDevice::Size(size) => format!("{:>width$}", size, width = size_col_width),
+            SizeOrDevice::Size(size) => {
+                format!("{:>width$}", opts.format_size(size), width = size_col_width)
+            }
             SizeOrDevice::Device { major, minor } => {
                 let dev_str = format!("{},", major);
                 let combined = format!(
@@ -430,12 +1178,136 @@ fn format_long_columns(infos: Vec<FileInfo>) -> String {
             }
         };
         output.push_str(&size_str);
-        output.push_str(&format!(" {} {}\n", info.modified_time, info.name));
+        output.push_str(&format!(
+            " {:<time_w$} {}\n",
+            info.time,
+            info.name,
+            time_w = max_time_width
+        ));
+        for (name, size) in &info.xattrs {
+            output.push_str(&format!("\t{}\t{:>4}\n", name, size));
+        }
     }
 
     output.trim_end().to_string()
 }
 
+/// Names of the fields of a JSON or CSV record, in order.
+const RECORD_FIELDS: [&str; 12] = [
+    "name",
+    "path",
+    "type",
+    "permissions",
+    "links",
+    "user",
+    "group",
+    "size",
+    "major",
+    "minor",
+    "mtime",
+    "target",
+];
+
+/// A value of a JSON or CSV record.
+enum Field {
+    Text(String),
+    Number(u64),
+    Null,
+}
+
+/// Returns the values for [`RECORD_FIELDS`]. Sizes are in bytes whatever
+/// the size options, and devices have a major and minor number instead.
+fn record_values(info: &FileInfo, tz: Tz) -> [Field; 12] {
+    let (size, major, minor) = match info.size_or_device {
+        SizeOrDevice::Size(size) => (Field::Number(size), Field::Null, Field::Null),
+        SizeOrDevice::Device { major, minor } => {
+            (Field::Null, Field::Number(major), Field::Number(minor))
+        }
+    };
+    let file_type = match info.permissions.chars().next() {
+        Some('d') => "directory",
+        Some('l') => "symlink",
+        Some('p') => "fifo",
+        Some('s') => "socket",
+        Some('b') => "block-device",
+        Some('c') => "char-device",
+        _ => "file",
+    };
+    let modified: DateTime<Utc> = info.modified.into();
+    [
+        Field::Text(info.sort_name.clone()),
+        Field::Text(info.path.to_string_lossy().into_owned()),
+        Field::Text(file_type.to_string()),
+        Field::Text(info.permissions.clone()),
+        Field::Number(info.links),
+        Field::Text(info.user.clone()),
+        Field::Text(info.group.clone()),
+        size,
+        major,
+        minor,
+        Field::Text(
+            modified
+                .with_timezone(&tz)
+                .to_rfc3339_opts(SecondsFormat::Secs, false),
+        ),
+        match &info.target {
+            Some(target) => Field::Text(target.to_string_lossy().into_owned()),
+            None => Field::Null,
+        },
+    ]
+}
+
+/// Formats an entry as a single-line JSON object.
+fn json_record(info: &FileInfo, tz: Tz) -> String {
+    let fields: Vec<String> = RECORD_FIELDS
+        .iter()
+        .zip(record_values(info, tz))
+        .map(|(name, value)| {
+            let value = match value {
+                Field::Text(text) => json_string(&text),
+                Field::Number(n) => n.to_string(),
+                Field::Null => "null".to_string(),
+            };
+            format!("\"{}\": {}", name, value)
+        })
+        .collect();
+    format!("  {{{}}}", fields.join(", "))
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Formats an entry as a CSV row, quoting fields as RFC 4180 requires.
+/// Missing values are left empty.
+fn csv_record(info: &FileInfo, tz: Tz) -> String {
+    let fields: Vec<String> = record_values(info, tz)
+        .into_iter()
+        .map(|value| match value {
+            Field::Text(text) if text.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            }
+            Field::Text(text) => text,
+            Field::Number(n) => n.to_string(),
+            Field::Null => String::new(),
+        })
+        .collect();
+    fields.join(",")
+}
+
 fn should_be_in_quotes(s: &str) -> bool {
     if s.is_empty() {
         return true;
@@ -515,11 +1387,39 @@ fn mode_string(meta: &std::fs::Metadata, path: &Path) -> String {
         file_type_char, ur, uw, ux, gr, gw, gx, or, ow, ox
     );
 
-    let has_acl = xattr::get(path, "system.posix_acl_access").is_ok_and(|v| v.is_some());
+    if has_xattr(path, "system.posix_acl_access") {
+        format!("{}+", base_mode)
+    } else if has_xattr(path, "security.selinux") {
+        format!("{}.", base_mode)
+    } else {
+        base_mode
+    }
+}
 
-    let acl_char = if has_acl { "+" } else { " " };
+fn has_xattr(path: &Path, name: &str) -> bool {
+    xattr::get(path, name).is_ok_and(|v| v.is_some())
+}
 
-    format!("{}{}", base_mode, acl_char)
+/// Returns the SELinux context of `path`, without following symlinks.
+fn security_context(path: &Path) -> Option<String> {
+    let value = xattr::get(path, "security.selinux").ok()??;
+    let context = String::from_utf8_lossy(&value);
+    Some(context.trim_end_matches('\0').to_string())
+}
+
+/// Returns the name and value size of each extended attribute of `path`.
+fn xattr_sizes(path: &Path) -> Vec<(String, usize)> {
+    let Ok(names) = xattr::list(path) else {
+        return Vec::new();
+    };
+    let mut xattrs: Vec<(String, usize)> = names
+        .map(|name| {
+            let size = xattr::get(path, &name).ok().flatten().map_or(0, |v| v.len());
+            (name.to_string_lossy().into_owned(), size)
+        })
+        .collect();
+    xattrs.sort();
+    xattrs
 }
 
 fn classify_suffix(file_type: &std::fs::FileType, meta: &std::fs::Metadata) -> String {
@@ -540,27 +1440,213 @@ fn classify_suffix(file_type: &std::fs::FileType, meta: &std::fs::Metadata) -> S
     }
 }
 
-pub fn filter_flags(args: Vec<String>) -> Option<(Vec<String>, bool, bool, bool)> {
-    let mut directories = vec![];
-    let mut show_hidden = false;
-    let mut long_format = false;
-    let mut classify = false;
+/// Long options that are the same as a short one.
+const LONG_ALIASES: &[(&str, char)] = &[
+    ("all", 'a'),
+    ("almost-all", 'A'),
+    ("ignore-backups", 'B'),
+    ("ignore", 'I'),
+    ("directory", 'd'),
+    ("inode", 'i'),
+    ("numeric-uid-gid", 'n'),
+    ("classify", 'F'),
+    ("reverse", 'r'),
+    ("recursive", 'R'),
+    ("human-readable", 'h'),
+    ("size", 's'),
+    ("context", 'Z'),
+    ("xattr", '@'),
+];
+
+const LONG_OPTIONS: &[&str] = &[
+    "hide",
+    "format",
+    "si",
+    "group-directories-first",
+    "full-time",
+    "sort",
+    "time",
+    "color",
+    "block-size",
+    "time-style",
+];
 
-    for arg in args {
-        if arg.starts_with('-') {
-            for c in arg.chars().skip(1) {
-                match c {
-                    'a' => show_hidden = true,
-                    'l' => long_format = true,
-                    'F' => classify = true,
-                    _ => return None,
+const USAGE: &str = "\
+Usage: ls [OPTION]... [FILE]...
+List information about the FILEs (the current directory by default).
+
+  -a, --all                  do not ignore entries starting with .
+  -A, --almost-all           do not list implied . and ..
+  -B, --ignore-backups       do not list entries ending with ~
+  -I, --ignore=PATTERN       do not list entries matching PATTERN
+      --hide=PATTERN         like -I, unless -a or -A is given
+  -l                         use a long listing format
+  -g, -o                     like -l, without the owner or group
+  -n, --numeric-uid-gid      like -l, with numeric user and group IDs
+  -C, -x, -1, -m             list down columns, across, one per line,
+                             or separated by commas
+      --format=WORD          across -x, commas -m, long -l, single-column -1,
+                             vertical -C, or json or csv for scripts
+  -d, --directory            list directories themselves
+  -F, --classify             append an indicator to entries
+  -i, --inode                print the inode number of each file
+  -s, --size                 print the allocated size of each file
+  -Z, --context              print the security context of each file
+  -@, --xattr                in long format, list extended attributes
+                             and their sizes under each file
+  -h, --human-readable       print sizes like 1K 234M 2G; --si uses 1000
+      --block-size=SIZE      scale sizes by SIZE
+  -R, --recursive            list subdirectories recursively
+  -r, --reverse              reverse the sort order
+  -t, -S, -X, -v, -U         sort by time, size, extension, version, or not
+      --sort=WORD            sort by WORD: name, time, size, extension,
+                             version or none
+      --group-directories-first
+                             list directories before files
+  -u, -c, --time=WORD        show and sort by access, change or birth time
+      --time-style=STYLE     full-iso, long-iso, iso or +FORMAT
+      --full-time            like -l --time-style=full-iso
+      --color[=WHEN]         color names: always, auto or never
+      --help                 display this help and exit
+      --version              output version information and exit
+";
+
+fn parse_args(args: Vec<String>) -> Result<LsOptions, i32> {
+    let mut opts = LsOptions::default();
+    let mut color = false;
+    let mut time_style = None;
+
+    let mut parser = Parser::new("ls", USAGE, args)
+        .aliases(LONG_ALIASES)
+        .longs(LONG_OPTIONS);
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Arg::Operand(path) => opts.paths.push(path),
+            Arg::Short(c) => match c {
+                'a' => opts.dot_files = DotFiles::All,
+                'A' => opts.dot_files = DotFiles::AlmostAll,
+                'B' => opts.ignore_backups = true,
+                'I' => opts.ignore.push(parser.value()?),
+                'l' => opts.format = Format::Long,
+                'C' => opts.format = Format::Columns,
+                'x' => opts.format = Format::Across,
+                '1' => opts.format = Format::SingleColumn,
+                'm' => opts.format = Format::Commas,
+                'd' => opts.directory = true,
+                'i' => opts.show_inode = true,
+                'Z' => opts.show_context = true,
+                '@' => opts.show_xattrs = true,
+                'n' => {
+                    opts.format = Format::Long;
+                    opts.numeric_ids = true;
                 }
-            }
-        } else {
-            directories.push(arg);
+                'g' => {
+                    opts.format = Format::Long;
+                    opts.hide_owner = true;
+                }
+                'o' => {
+                    opts.format = Format::Long;
+                    opts.hide_group = true;
+                }
+                'F' => opts.classify = true,
+                't' => opts.sort = SortKey::Time,
+                'S' => opts.sort = SortKey::Size,
+                'X' => opts.sort = SortKey::Extension,
+                'v' => opts.sort = SortKey::Version,
+                'U' => opts.sort = SortKey::None,
+                'r' => opts.reverse = true,
+                'R' => opts.recursive = true,
+                'h' => opts.size_unit = Some(SizeUnit::Human { base: 1024 }),
+                's' => opts.show_blocks = true,
+                'u' => opts.time_field = TimeField::Accessed,
+                'c' => opts.time_field = TimeField::Changed,
+                _ => return Err(parser.unexpected(Arg::Short(c))),
+            },
+            Arg::Long(name) => match name.as_str() {
+                "format" => {
+                    let word = parser.value()?;
+                    opts.format = match word.as_str() {
+                        "vertical" => Format::Columns,
+                        "across" | "horizontal" => Format::Across,
+                        "single-column" => Format::SingleColumn,
+                        "commas" => Format::Commas,
+                        "long" | "verbose" => Format::Long,
+                        "json" => Format::Json,
+                        "csv" => Format::Csv,
+                        _ => return Err(parser.invalid_value(&word)),
+                    };
+                }
+                "si" => opts.size_unit = Some(SizeUnit::Human { base: 1000 }),
+                "hide" => opts.hide.push(parser.value()?),
+                "group-directories-first" => opts.group_directories_first = true,
+                "full-time" => {
+                    opts.format = Format::Long;
+                    time_style = TimeStyle::parse("full-iso");
+                }
+                "sort" => {
+                    let word = parser.value()?;
+                    opts.sort = match word.as_str() {
+                        "name" => SortKey::Name,
+                        "time" => SortKey::Time,
+                        "size" => SortKey::Size,
+                        "extension" => SortKey::Extension,
+                        "version" => SortKey::Version,
+                        "none" => SortKey::None,
+                        _ => return Err(parser.invalid_value(&word)),
+                    };
+                }
+                "time" => {
+                    let word = parser.value()?;
+                    opts.time_field = match word.as_str() {
+                        "mtime" | "modification" => TimeField::Modified,
+                        "atime" | "access" | "use" => TimeField::Accessed,
+                        "ctime" | "status" => TimeField::Changed,
+                        "birth" | "creation" => TimeField::Birth,
+                        _ => return Err(parser.invalid_value(&word)),
+                    };
+                }
+                "color" => {
+                    color = match parser.optional_value().as_deref() {
+                        None | Some("always" | "yes" | "force") => true,
+                        Some("never" | "no" | "none") => false,
+                        Some("auto" | "tty" | "if-tty") => io::stdout().is_terminal(),
+                        Some(when) => return Err(parser.invalid_value(when)),
+                    };
+                }
+                "block-size" => {
+                    let spec = parser.value()?;
+                    match SizeUnit::parse(&spec) {
+                        Some(unit) => opts.size_unit = Some(unit),
+                        None => return Err(parser.invalid_value(&spec)),
+                    }
+                }
+                "time-style" => {
+                    let style = parser.value()?;
+                    match TimeStyle::parse(&style) {
+                        Some(style) => time_style = Some(style),
+                        None => return Err(parser.invalid_value(&style)),
+                    }
+                }
+                _ => return Err(parser.unexpected(Arg::Long(name))),
+            },
         }
     }
-    Some((directories, show_hidden, long_format, classify))
+    if color {
+        opts.colors = Some(Colors::from_env());
+    }
+    opts.time_style = match time_style {
+        Some(style) => style,
+        None => match env::var("TIME_STYLE") {
+            Ok(style) => TimeStyle::parse(&style).unwrap_or_default(),
+            Err(_) => TimeStyle::default(),
+        },
+    };
+    opts.tz = local_tz();
+    // Like GNU, `-u` and `-c` also sort by that time outside long format.
+    if opts.time_field != TimeField::Modified && !opts.long() && opts.sort == SortKey::Name {
+        opts.sort = SortKey::Time;
+    }
+    Ok(opts)
 }
 
 fn major(dev: u64) -> u64 {
@@ -571,27 +1657,62 @@ fn minor(dev: u64) -> u64 {
     (dev & 0xff) | ((dev >> 12) & 0xfff00)
 }
 
-fn format_columns(items: Vec<String>) -> String {
+/// Joins names with `, `, starting a new line before a name that would
+/// not fit in the terminal width.
+fn format_commas(items: Vec<String>) -> String {
+    let term_width = terminal_width();
+    let mut output = String::new();
+    let mut line_len = 0;
+    for (i, item) in items.iter().enumerate() {
+        let last = i + 1 == items.len();
+        let len = visible_len(item) + usize::from(!last);
+        if i > 0 {
+            if line_len + 1 + len > term_width {
+                output.push('\n');
+                line_len = 0;
+            } else {
+                output.push(' ');
+                line_len += 1;
+            }
+        }
+        output.push_str(item);
+        if !last {
+            output.push(',');
+        }
+        line_len += len;
+    }
+    output
+}
+
+fn terminal_width() -> usize {
+    terminal_size().map_or(80, |(Width(w), _)| w as usize)
+}
+
+fn format_columns(items: Vec<String>, across: bool) -> String {
     if items.is_empty() {
         return String::new();
     }
-    let term_width = terminal_size().map_or(80, |(Width(w), _)| w as usize);
+    let term_width = terminal_width();
     let n_items = items.len();
 
     let mut best_cols = 1;
     for cols in (1..=n_items).rev() {
-        let rows = (n_items + cols - 1) / cols;
+        let rows = n_items.div_ceil(cols);
         let mut col_widths = vec![0; cols];
         let mut total_width = 0;
         let mut possible = true;
-        for col in 0..cols {
+        for (col, width) in col_widths.iter_mut().enumerate() {
             for row in 0..rows {
-                let i = col * rows + row;
+                let i = if across {
+                    row * cols + col
+                } else {
+                    col * rows + row
+                };
                 if i < n_items {
-                    col_widths[col] = col_widths[col].max(items[i].len());
+                    *width = (*width).max(visible_len(&items[i]));
                 }
             }
-            total_width += col_widths[col];
+            total_width += *width;
             if col > 0 {
                 total_width += 2;
             }
@@ -606,32 +1727,57 @@ fn format_columns(items: Vec<String>) -> String {
         }
     }
 
-    let rows = (n_items + best_cols - 1) / best_cols;
+    let rows = n_items.div_ceil(best_cols);
     let mut col_widths = vec![0; best_cols];
-    for col in 0..best_cols {
+    for (col, width) in col_widths.iter_mut().enumerate() {
         for row in 0..rows {
-            let i = col * rows + row;
+            let i = if across {
+                row * best_cols + col
+            } else {
+                col * rows + row
+            };
             if i < n_items {
-                col_widths[col] = col_widths[col].max(items[i].len());
+                *width = (*width).max(visible_len(&items[i]));
             }
         }
     }
 
     let mut output = String::new();
     for row in 0..rows {
-        for col in 0..best_cols {
-            let i = col * rows + row;
+        for (col, width) in col_widths.iter().enumerate() {
+            let i = if across {
+                row * best_cols + col
+            } else {
+                col * rows + row
+            };
             if i < n_items {
                 let s = &items[i];
                 output.push_str(s);
                 if col < best_cols - 1 {
-                    let padding = col_widths[col] - s.len();
+                    let padding = width - visible_len(s);
                     output.push_str(&" ".repeat(padding + 2));
                 }
             }
         }
+        // Rows that end before the last column keep no padding.
+        output.truncate(output.trim_end_matches(' ').len());
         output.push('\n');
     }
 
     output.trim_end().to_string()
 }
+
+/// Returns the number of characters `s` takes on screen, skipping color
+/// escape sequences.
+fn visible_len(s: &str) -> usize {
+    let mut len = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            len += 1;
+        }
+    }
+    len
+}
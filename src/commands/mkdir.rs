@@ -1,28 +1,228 @@
+use super::options::{Arg, Parser};
+use super::strerror;
 use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+struct MkdirOptions {
+    parents: bool,
+    verbose: bool,
+    mode: Option<u32>,
+}
+
+const USAGE: &str = "\
+Usage: mkdir [OPTION]... DIRECTORY...
+Create the DIRECTORY(ies), if they do not already exist.
+
+  -m, --mode=MODE   set file mode (as in chmod), not a=rwx - umask
+  -p, --parents     no error if existing, make parent directories as needed
+  -v, --verbose     print a message for each created directory
+      --help        display this help and exit
+      --version     output version information and exit
+";
+
+const LONG_ALIASES: &[(&str, char)] = &[("mode", 'm'), ("parents", 'p'), ("verbose", 'v')];
+
 /// Creates directories specified in the argument vector.
 ///
 /// # Arguments
 ///
-/// * `args` - Vector of strings representing directory names to create.
+/// * `args` - Vector of options followed by directory names to create.
+///
+/// # Options
+///
+/// - `-p`: create missing parent directories; existing directories are
+///   not an error.
+/// - `-m MODE`: set the mode of the created directories, either octal
+///   (`755`) or symbolic (`u=rwx,go=rx`, `a+w`, `g-x`).
+/// - `-v`: print a message for each created directory.
+/// - GNU long forms such as `--parents`, and `--help`.
 ///
 /// # Behavior
 ///
 /// - Prints an error if no arguments are given.
-/// - Attempts to create each directory individually.
-/// - Prints an error message if creation of any directory fails.
+/// - Attempts to create each directory individually, reporting failures in
+///   GNU style and continuing with the rest.
 ///
 /// # Example
 ///
 /// ```
 /// mkdir(vec!["dir1".to_string(), "dir2".to_string()]);
+/// mkdir(vec!["-pv".to_string(), "-m".to_string(), "700".to_string(), "a/b/c".to_string()]);
 /// ```
-pub fn mkdir(args:Vec<String>){
-    if args.len() ==0 {
-                 eprintln!("mkdir: missing argupments")
+pub fn mkdir(args: Vec<String>) -> i32 {
+    let (opts, dirs) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(status) => return status,
+    };
+    if dirs.is_empty() {
+        eprintln!("mkdir: missing operand");
+        return 1;
+    }
+
+    let mut status = 0;
+    for dir in dirs {
+        let path = Path::new(&dir);
+        let result = if opts.parents {
+            create_parents(path, opts.verbose)
+        } else {
+            create(path, opts.verbose)
+        };
+        let result = result.and_then(|created| match (created, opts.mode) {
+            (true, Some(m)) => fs::set_permissions(path, fs::Permissions::from_mode(m)),
+            _ => Ok(()),
+        });
+        if let Err(e) = result {
+            eprintln!("mkdir: cannot create directory '{}': {}", dir, strerror(&e));
+            status = 1;
+        }
+    }
+    status
+}
+
+fn parse_args(args: Vec<String>) -> Result<(MkdirOptions, Vec<String>), i32> {
+    let mut opts = MkdirOptions::default();
+    let mut dirs = Vec::new();
+
+    let mut parser = Parser::new("mkdir", USAGE, args).aliases(LONG_ALIASES);
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Arg::Operand(dir) => dirs.push(dir),
+            Arg::Short('p') => opts.parents = true,
+            Arg::Short('v') => opts.verbose = true,
+            Arg::Short('m') => {
+                let value = parser.value()?;
+                match parse_mode(&value, umask()) {
+                    Some(m) => opts.mode = Some(m),
+                    None => {
+                        eprintln!("mkdir: invalid mode '{}'", value);
+                        return Err(1);
+                    }
+                }
+            }
+            arg => return Err(parser.unexpected(arg)),
+        }
+    }
+    Ok((opts, dirs))
+}
+
+/// Creates a single directory, returning `true` once it exists.
+fn create(path: &Path, verbose: bool) -> io::Result<bool> {
+    fs::create_dir(path)?;
+    if verbose {
+        println!("mkdir: created directory '{}'", path.display());
     }
-    for dir in args {
-        if let Err(e) = fs::create_dir(&dir) {
-            eprintln!("mkdir: {dir}: {e}, {}", e.kind())
+    Ok(true)
+}
+
+/// Creates `path` and any missing ancestors.
+///
+/// Returns whether `path` itself was created; an existing directory is not
+/// an error.
+fn create_parents(path: &Path, verbose: bool) -> io::Result<bool> {
+    let mut current = PathBuf::new();
+    let mut created = false;
+    for comp in path.components() {
+        current.push(comp);
+        created = match fs::create_dir(&current) {
+            Ok(()) => {
+                if verbose {
+                    println!("mkdir: created directory '{}'", current.display());
+                }
+                true
+            }
+            Err(_) if current.is_dir() => false,
+            Err(e) => return Err(e),
+        };
+    }
+    Ok(created)
+}
+
+/// Parses an octal or symbolic mode, starting from `a=rwx`.
+///
+/// Symbolic clauses without a `who` part are limited by `umask`, like
+/// `chmod` does.
+fn parse_mode(spec: &str, umask: u32) -> Option<u32> {
+    if !spec.is_empty() && spec.chars().all(|c| c.is_digit(8)) {
+        return u32::from_str_radix(spec, 8).ok().filter(|m| *m <= 0o7777);
+    }
+
+    let mut mode: u32 = 0o777;
+    for clause in spec.split(',') {
+        let who_len = clause
+            .find(|c: char| !"ugoa".contains(c))
+            .unwrap_or(clause.len());
+        let (who, mut rest) = clause.split_at(who_len);
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut mask = 0;
+        for c in who.chars() {
+            mask |= match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                _ => 0o7777,
+            };
+        }
+        let (mask, limit) = if who.is_empty() {
+            (0o7777, !umask)
+        } else {
+            (mask, !0)
+        };
+
+        while let Some(op) = rest.chars().next() {
+            if !"+-=".contains(op) {
+                return None;
+            }
+            rest = &rest[1..];
+            let perm_len = rest.find(['+', '-', '=']).unwrap_or(rest.len());
+            let (perms, next) = rest.split_at(perm_len);
+            rest = next;
+
+            let mut bits = 0;
+            for p in perms.chars() {
+                bits |= match p {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' | 'X' => 0o111,
+                    's' => 0o6000,
+                    't' => 0o1000,
+                    'u' => copy_class(mode, 6),
+                    'g' => copy_class(mode, 3),
+                    'o' => copy_class(mode, 0),
+                    _ => return None,
+                };
+            }
+            bits &= mask & limit;
+            match op {
+                '+' => mode |= bits,
+                '-' => mode &= !bits,
+                _ => mode = (mode & !mask) | bits,
+            }
         }
     }
+    Some(mode)
+}
+
+/// Spreads the `rwx` bits of one class (at `shift`) over all three classes.
+fn copy_class(mode: u32, shift: u32) -> u32 {
+    let bits = (mode >> shift) & 0o7;
+    bits << 6 | bits << 3 | bits
+}
+
+/// Reads the process umask from `/proc/self/status`, defaulting to `022`.
+fn umask() -> u32 {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|l| l.strip_prefix("Umask:"))
+                .and_then(|m| u32::from_str_radix(m.trim(), 8).ok())
+        })
+        .unwrap_or(0o022)
 }
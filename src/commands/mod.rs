@@ -1,10 +1,38 @@
 pub mod cat;
 pub mod cd;
 pub mod cp;
+pub mod dirs;
 pub mod echo;
 pub mod clear;
 pub mod  ls;
 pub mod mkdir;
 pub mod mv;
+pub mod options;
+pub mod printf;
 pub mod pwd;
 pub mod rm;
+pub mod trash;
+
+use std::io::{self, Write};
+
+/// Asks a yes/no question on stderr and reads the answer from stdin.
+///
+/// Returns `true` only if the answer starts with `y` or `Y`.
+pub fn confirm(question: &str) -> bool {
+    eprint!("{} ", question);
+    io::stderr().flush().ok();
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => answer.trim_start().starts_with(['y', 'Y']),
+        Err(_) => false,
+    }
+}
+
+/// Formats an I/O error like `strerror`, without Rust's `(os error N)`.
+pub fn strerror(e: &io::Error) -> String {
+    let msg = e.to_string();
+    match msg.find(" (os error") {
+        Some(i) => msg[..i].to_string(),
+        None => msg,
+    }
+}
@@ -1,95 +1,348 @@
-use std::{fs, path::Path};
-// use std::path::Path;
+use super::confirm;
+use super::cp::{copy_path, CopyOptions};
+use super::options::{Arg, Parser};
+use super::strerror;
+use std::os::unix::fs::MetadataExt;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How an existing destination is backed up before being replaced.
+#[derive(Clone, Copy, PartialEq)]
+enum Backup {
+    None,
+    /// Append the suffix, `~` by default.
+    Simple,
+    /// Append `.~N~` with the next free number.
+    Numbered,
+    /// Numbered if numbered backups already exist, simple otherwise.
+    Existing,
+}
+
+struct MvOptions {
+    interactive: bool,
+    no_clobber: bool,
+    verbose: bool,
+    backup: Backup,
+    suffix: String,
+}
+
+const USAGE: &str = "\
+Usage: mv [OPTION]... SOURCE DEST
+  or:  mv [OPTION]... SOURCE... DIRECTORY
+  or:  mv [OPTION]... -t DIRECTORY SOURCE...
+Rename SOURCE to DEST, or move SOURCE(s) to DIRECTORY.
+
+      --backup[=CONTROL]       make a backup of each existing destination file
+  -b                           like --backup but does not accept an argument
+  -f, --force                  do not prompt before overwriting
+  -i, --interactive            prompt before overwrite
+  -n, --no-clobber             do not overwrite an existing file
+  -S, --suffix=SUFFIX          override the usual backup suffix
+  -t, --target-directory=DIRECTORY  move all SOURCE arguments into DIRECTORY
+  -v, --verbose                explain what is being done
+      --help                   display this help and exit
+      --version                output version information and exit
+";
+
+const LONG_ALIASES: &[(&str, char)] = &[
+    ("force", 'f'),
+    ("interactive", 'i'),
+    ("no-clobber", 'n'),
+    ("suffix", 'S'),
+    ("target-directory", 't'),
+    ("verbose", 'v'),
+];
+
+const LONG_OPTIONS: &[&str] = &["backup"];
+
 /// Moves or renames files and directories.
 ///
 /// # Arguments
 ///
 /// * `args` - Vector of command-line arguments where:
 ///   - All but last are source paths.
-///   - Last is the destination path.
+///   - Last is the destination path, unless `-t DIR` is given.
+///
+/// # Options
+///
+/// - `-i`: prompt before overwriting; `-n`: never overwrite; `-f`: never
+///   prompt. The last of the three wins.
+/// - `-b`, `--backup[=CONTROL]`: back up an existing destination first.
+///   CONTROL is `none`/`off`, `simple`/`never`, `numbered`/`t` or
+///   `existing`/`nil`; it defaults to `$VERSION_CONTROL` or `existing`.
+/// - `-S SUFFIX`, `--suffix=SUFFIX`: simple backup suffix, defaulting to
+///   `$SIMPLE_BACKUP_SUFFIX` or `~`.
+/// - `-t DIR`, `--target-directory=DIR`: move all operands into `DIR`.
+/// - `-v`: print each rename.
+/// - `--help`, `--version`: print usage or version information.
 ///
 /// # Behavior
 ///
 /// - If multiple sources, destination must be an existing directory.
 /// - If single source and destination does not exist, renames source to destination.
-/// - Prints error messages for invalid arguments or I/O errors.
+/// - Falls back to copy-and-delete when source and destination are on
+///   different filesystems.
+/// - A failing source is reported and skipped; the rest are still moved.
+///
+/// # Returns
+///
+/// `0` if every source was moved, `1` otherwise.
 ///
 /// # Example
 ///
 /// ```
 /// mv(vec!["file1.txt".to_string(), "file2.txt".to_string(), "dir".to_string()]);
-/// mv(vec!["oldname.txt".to_string(), "newname.txt".to_string()]);
+/// mv(vec!["--backup=numbered".to_string(), "new.txt".to_string(), "old.txt".to_string()]);
 /// ```
-pub fn mv(args: Vec<String>) {
-    if args.len() < 2 {
-        eprintln!("mv: missing file operand or destination");
-        return;
-    }
-
-    let source_paths: Vec<&Path> = args[0..args.len() - 1].iter().map(Path::new).collect();
-    let dest_path = Path::new(&args[args.len() - 1]);
+pub fn mv(args: Vec<String>) -> i32 {
+    let (opts, target_dir, mut operands) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(status) => return status,
+    };
 
-    let handle_rename = |res: Result<(), std::io::Error>, src_disp: &str, dest_disp: &str| {
-        if let Err(e) = res {
-            eprintln!("mv: cannot move '{}' to '{}': {}", src_disp, dest_disp, e);
-            return false;
+    let (sources, dest_path) = match target_dir {
+        Some(dir) => {
+            if !Path::new(&dir).is_dir() {
+                eprintln!("mv: target directory '{}' is not a directory", dir);
+                return 1;
+            }
+            (operands, PathBuf::from(dir))
+        }
+        None => {
+            if operands.len() < 2 {
+                eprintln!("mv: missing file operand or destination");
+                return 1;
+            }
+            let dest = PathBuf::from(operands.pop().unwrap_or_default());
+            (operands, dest)
         }
-        true
     };
+    if sources.is_empty() {
+        eprintln!("mv: missing file operand");
+        return 1;
+    }
 
-    if source_paths.len() > 1 {
-        if !dest_path.is_dir() {
-            eprintln!("mv: target '{}' is not a directory", dest_path.display());
-            return;
-        }
+    let into_dir = dest_path.is_dir();
+    if sources.len() > 1 && !into_dir {
+        eprintln!("mv: target '{}' is not a directory", dest_path.display());
+        return 1;
+    }
 
-        for src_path in source_paths {
-            let file_name = match src_path.file_name() {
-                Some(name) => name,
+    let mut status = 0;
+    for src in &sources {
+        let src_path = Path::new(src);
+        let final_dest = if into_dir {
+            match src_path.file_name() {
+                Some(name) => dest_path.join(name),
                 None => {
-                    eprintln!(
-                        "mv: cannot stat '{}': No such file or directory",
-                        src_path.display()
-                    );
-                    return;
+                    eprintln!("mv: cannot move '{}': Invalid argument", src);
+                    status = 1;
+                    continue;
                 }
-            };
-            let final_dest = dest_path.join(file_name);
-
-            if !handle_rename(
-                fs::rename(src_path, &final_dest),
-                &src_path.to_string_lossy(),
-                &final_dest.to_string_lossy(),
-            ) {
-                return;
             }
+        } else {
+            dest_path.clone()
+        };
+
+        if let Err(e) = move_one(src_path, &final_dest, &opts) {
+            eprintln!("mv: {}", e);
+            status = 1;
         }
-    } else {
-        let src_path = source_paths[0];
+    }
+    status
+}
 
-        if dest_path.is_dir() {
-            let file_name = match src_path.file_name() {
-                Some(name) => name,
-                None => {
-                    eprintln!(
-                        "mv: cannot stat '{}': No such file or directory",
-                        src_path.display()
-                    );
-                    return;
+/// Returns the options, the `-t` directory if any, and the operands.
+fn parse_args(args: Vec<String>) -> Result<(MvOptions, Option<String>, Vec<String>), i32> {
+    let mut opts = MvOptions {
+        interactive: false,
+        no_clobber: false,
+        verbose: false,
+        backup: Backup::None,
+        suffix: env::var("SIMPLE_BACKUP_SUFFIX").unwrap_or_else(|_| "~".to_string()),
+    };
+    let mut target_dir = None;
+    let mut operands = Vec::new();
+
+    let mut parser = Parser::new("mv", USAGE, args)
+        .aliases(LONG_ALIASES)
+        .longs(LONG_OPTIONS);
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Arg::Operand(operand) => operands.push(operand),
+            Arg::Short(c @ ('f' | 'i' | 'n')) => set_mode(&mut opts, c),
+            Arg::Short('v') => opts.verbose = true,
+            Arg::Short('b') => opts.backup = parse_backup(None).unwrap_or(Backup::Existing),
+            Arg::Short('S') => opts.suffix = parser.value()?,
+            Arg::Short('t') => target_dir = Some(parser.value()?),
+            Arg::Long(ref l) if l == "backup" => {
+                let value = parser.optional_value();
+                match parse_backup(value.as_deref()) {
+                    Some(b) => opts.backup = b,
+                    None => return Err(parser.invalid_value(&value.unwrap_or_default())),
                 }
-            };
-            let final_dest = dest_path.join(file_name);
-            handle_rename(
-                fs::rename(src_path, &final_dest),
-                &src_path.to_string_lossy(),
-                &final_dest.to_string_lossy(),
-            );
-        } else {
-            handle_rename(
-                fs::rename(src_path, dest_path),
-                &src_path.to_string_lossy(),
-                &dest_path.to_string_lossy(),
-            );
+            }
+            arg => return Err(parser.unexpected(arg)),
+        }
+    }
+    Ok((opts, target_dir, operands))
+}
+
+/// Moves a single source, applying the overwrite and backup options.
+fn move_one(src: &Path, dst: &Path, opts: &MvOptions) -> Result<(), String> {
+    let src_meta = fs::symlink_metadata(src)
+        .map_err(|e| format!("cannot stat '{}': {}", src.display(), strerror(&e)))?;
+
+    let mut backup_path = None;
+    if let Ok(dst_meta) = fs::symlink_metadata(dst) {
+        if dst_meta.dev() == src_meta.dev() && dst_meta.ino() == src_meta.ino() {
+            return Err(format!(
+                "'{}' and '{}' are the same file",
+                src.display(),
+                dst.display()
+            ));
         }
+        if opts.no_clobber {
+            return Ok(());
+        }
+        if opts.interactive && !confirm(&format!("mv: overwrite '{}'?", dst.display())) {
+            return Ok(());
+        }
+        if opts.backup != Backup::None {
+            let backup = backup_name(dst, opts.backup, &opts.suffix);
+            fs::rename(dst, &backup).map_err(|e| {
+                format!("cannot backup '{}': {}", dst.display(), strerror(&e))
+            })?;
+            backup_path = Some(backup);
+        }
+    }
+
+    if let Err(e) = move_path(src, dst) {
+        // Put the destination back so a failed move does not leave it only
+        // under its backup name.
+        if let Some(backup) = &backup_path {
+            fs::rename(backup, dst).ok();
+        }
+        return Err(format!(
+            "cannot move '{}' to '{}': {}",
+            src.display(),
+            dst.display(),
+            strerror(&e)
+        ));
+    }
+
+    if opts.verbose {
+        match backup_path {
+            Some(b) => println!(
+                "renamed '{}' -> '{}' (backup: '{}')",
+                src.display(),
+                dst.display(),
+                b.display()
+            ),
+            None => println!("renamed '{}' -> '{}'", src.display(), dst.display()),
+        }
+    }
+    Ok(())
+}
+
+/// Applies `-f`, `-i` or `-n`; each one cancels the other two.
+fn set_mode(opts: &mut MvOptions, flag: char) {
+    opts.interactive = flag == 'i';
+    opts.no_clobber = flag == 'n';
+}
+
+/// Parses a `--backup` CONTROL value, falling back to `$VERSION_CONTROL`.
+fn parse_backup(value: Option<&str>) -> Option<Backup> {
+    let control = match value {
+        Some(v) => v.to_string(),
+        None => env::var("VERSION_CONTROL").unwrap_or_else(|_| "existing".to_string()),
+    };
+    match control.as_str() {
+        "none" | "off" => Some(Backup::None),
+        "simple" | "never" => Some(Backup::Simple),
+        "numbered" | "t" => Some(Backup::Numbered),
+        "existing" | "nil" | "" => Some(Backup::Existing),
+        _ => None,
+    }
+}
+
+/// Picks the backup file name for `path`.
+fn backup_name(path: &Path, backup: Backup, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let prefix = format!("{}.~", name);
+
+    let highest = fs::read_dir(if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    })
+    .map(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|e| {
+                let n = e.file_name().to_string_lossy().into_owned();
+                n.strip_prefix(&prefix)?.strip_suffix('~')?.parse::<u64>().ok()
+            })
+            .max()
+            .unwrap_or(0)
+    })
+    .unwrap_or(0);
+
+    let numbered = match backup {
+        Backup::Numbered => true,
+        Backup::Existing => highest > 0,
+        _ => false,
+    };
+    if numbered {
+        path.with_file_name(format!("{}{}~", prefix, highest + 1))
+    } else {
+        path.with_file_name(format!("{}{}", name, suffix))
+    }
+}
+
+/// Renames `src` to `dst`, copying across filesystems when needed.
+///
+/// When `rename` fails with `EXDEV`, the source is copied (recursively,
+/// keeping permissions, ownership, timestamps, xattrs and symlinks) to a
+/// temporary name next to `dst`, renamed into place and only then removed.
+/// If the copy fails halfway the temporary copy is deleted and the source
+/// is left untouched.
+pub fn move_path(src: &Path, dst: &Path) -> io::Result<()> {
+    match fs::rename(src, dst) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+        other => return other,
+    }
+
+    let name = dst.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dst.with_file_name(format!(".{}.mv-{}", name, std::process::id()));
+    let opts = CopyOptions {
+        recursive: true,
+        preserve: true,
+        xattrs: true,
+        dereference: Some(false),
+        ..CopyOptions::default()
+    };
+
+    let copied = match copy_path(src, &tmp, &opts, true) {
+        Ok(()) => fs::rename(&tmp, dst),
+        // The move is abandoned, so only the first failure is reported.
+        Err(mut errors) => Err(errors.remove(0)),
+    };
+    if let Err(e) = copied {
+        remove_path(&tmp).ok();
+        return Err(e);
+    }
+
+    remove_path(src)
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
     }
 }
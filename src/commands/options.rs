@@ -0,0 +1,391 @@
+/// A command-line argument returned by [`Parser::next`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    /// A single-character option, e.g. `l` from `-l` or from `-la`.
+    Short(char),
+    /// A long option name without the dashes or any `=value`.
+    Long(String),
+    /// Anything else, including `-` and everything after `--`.
+    Operand(String),
+}
+
+/// Splits a built-in's arguments into options and operands the way GNU
+/// `getopt_long` does.
+///
+/// Short options may be bundled (`-la`) and take their value from the rest
+/// of the bundle or the next argument (`-Ifoo`, `-I foo`); long options
+/// take it after `=` or from the next argument. `--` ends the options.
+/// `--help` and `--version` are answered by the parser itself, and long
+/// options can be declared as aliases of short ones. A long option may be
+/// abbreviated to any unambiguous prefix of a declared one (`--verb`).
+///
+/// Errors are printed in GNU style, and reported to the caller as the exit
+/// status to return.
+///
+/// # Example
+///
+/// ```
+/// let mut parser = Parser::new("cmd", USAGE, args).longs(&["verbose"]);
+/// while let Some(arg) = parser.next()? {
+///     match arg {
+///         Arg::Short('v') => verbose = true,
+///         Arg::Long(ref l) if l == "verbose" => verbose = true,
+///         Arg::Short('o') => output = Some(parser.value()?),
+///         Arg::Operand(path) => paths.push(path),
+///         arg => return Err(parser.unexpected(arg)),
+///     }
+/// }
+/// ```
+pub struct Parser {
+    command: &'static str,
+    usage: &'static str,
+    args: std::vec::IntoIter<String>,
+    /// Characters of a short option bundle not returned yet.
+    shorts: Vec<char>,
+    /// The last long option and the value given after its `=`, if any.
+    long_value: Option<(String, String)>,
+    /// The option most recently returned, for error messages.
+    last: Option<Arg>,
+    /// Long options returned as their short equivalent.
+    aliases: &'static [(&'static str, char)],
+    /// Long options without a short equivalent, for resolving prefixes.
+    longs: &'static [&'static str],
+    options_done: bool,
+}
+
+impl Parser {
+    /// Creates a parser for `command`, whose `--help` prints `usage`.
+    pub fn new(command: &'static str, usage: &'static str, args: Vec<String>) -> Parser {
+        Parser {
+            command,
+            usage,
+            args: args.into_iter(),
+            shorts: Vec::new(),
+            long_value: None,
+            last: None,
+            aliases: &[],
+            longs: &[],
+            options_done: false,
+        }
+    }
+
+    /// Makes each long option in `aliases` come back as the short option
+    /// it is paired with, so commands only match the short form.
+    pub fn aliases(mut self, aliases: &'static [(&'static str, char)]) -> Parser {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Declares the long options that have no short form, so abbreviations
+    /// of them resolve to the full name.
+    pub fn longs(mut self, longs: &'static [&'static str]) -> Parser {
+        self.longs = longs;
+        self
+    }
+
+    /// Returns the next option or operand, or `None` at the end.
+    ///
+    /// `Err` carries the exit status after `--help` or `--version` was
+    /// answered (`0`), or a long option was ambiguous or given a value it
+    /// does not take (`1`).
+    pub fn next(&mut self) -> Result<Option<Arg>, i32> {
+        if let Some((name, _)) = self.long_value.take() {
+            return Err(self.fail(&format!("option '--{}' doesn't allow an argument", name)));
+        }
+        if !self.shorts.is_empty() {
+            return Ok(Some(self.next_short()));
+        }
+        let Some(arg) = self.args.next() else {
+            return Ok(None);
+        };
+        if self.options_done || arg == "-" || !arg.starts_with('-') {
+            return Ok(Some(Arg::Operand(arg)));
+        }
+        if arg == "--" {
+            self.options_done = true;
+            return self.next();
+        }
+        let Some(long) = arg.strip_prefix("--") else {
+            self.shorts = arg.chars().skip(1).collect();
+            return Ok(Some(self.next_short()));
+        };
+
+        let (name, value) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, None),
+        };
+        let name = self.resolve(name)?;
+        match (name, value) {
+            ("help", None) => {
+                print!("{}", self.usage);
+                return Err(0);
+            }
+            ("version", None) => {
+                println!(
+                    "{} (kinda_shell) {}",
+                    self.command,
+                    env!("CARGO_PKG_VERSION")
+                );
+                return Err(0);
+            }
+            _ => {}
+        }
+        self.long_value = value.map(|v| (name.to_string(), v.to_string()));
+        let long = self.option(Arg::Long(name.to_string()));
+        match self.aliases.iter().find(|(alias, _)| *alias == name) {
+            Some(&(_, c)) => Ok(Some(Arg::Short(c))),
+            None => Ok(Some(long)),
+        }
+    }
+
+    /// Returns the value of the option just returned by [`Parser::next`]:
+    /// the rest of a short bundle, the part after `=`, or the next argument.
+    pub fn value(&mut self) -> Result<String, i32> {
+        if let Some((_, value)) = self.long_value.take() {
+            return Ok(value);
+        }
+        if !self.shorts.is_empty() {
+            return Ok(self.shorts.drain(..).collect());
+        }
+        if let Some(value) = self.args.next() {
+            return Ok(value);
+        }
+        let message = match &self.last {
+            Some(Arg::Long(name)) => format!("option '--{}' requires an argument", name),
+            Some(Arg::Short(c)) => format!("option requires an argument -- '{}'", c),
+            _ => "missing argument".to_string(),
+        };
+        Err(self.fail(&message))
+    }
+
+    /// Returns the value of the option just returned if it was given as
+    /// `--option=value`; for options whose value may be left out.
+    pub fn optional_value(&mut self) -> Option<String> {
+        self.long_value.take().map(|(_, value)| value)
+    }
+
+    /// Reports an argument the command does not accept and returns the
+    /// exit status to use.
+    pub fn unexpected(&self, arg: Arg) -> i32 {
+        let message = match arg {
+            Arg::Short(c) => format!("invalid option -- '{}'", c),
+            Arg::Long(name) => format!("unrecognized option '--{}'", name),
+            Arg::Operand(operand) => format!("extra operand '{}'", operand),
+        };
+        self.fail(&message)
+    }
+
+    /// Reports a bad value for the option just returned and returns the
+    /// exit status to use.
+    pub fn invalid_value(&self, value: &str) -> i32 {
+        let option = match &self.last {
+            Some(Arg::Long(name)) => format!("--{}", name),
+            Some(Arg::Short(c)) => format!("-{}", c),
+            _ => String::new(),
+        };
+        self.fail(&format!("invalid argument '{}' for '{}'", value, option))
+    }
+
+    /// Expands `name` to the declared long option it abbreviates. Unknown
+    /// names are returned as given, for the command to reject.
+    fn resolve<'a>(&self, name: &'a str) -> Result<&'a str, i32> {
+        let names = self.aliases.iter().map(|&(alias, _)| alias);
+        let names = names
+            .chain(self.longs.iter().copied())
+            .chain(["help", "version"]);
+        let matches: Vec<&str> = names.filter(|n| n.starts_with(name)).collect();
+        if matches.contains(&name) {
+            return Ok(name);
+        }
+        match matches[..] {
+            [] => Ok(name),
+            [only] => Ok(only),
+            _ => {
+                let possibilities: Vec<String> =
+                    matches.iter().map(|n| format!("'--{}'", n)).collect();
+                Err(self.fail(&format!(
+                    "option '--{}' is ambiguous; possibilities: {}",
+                    name,
+                    possibilities.join(" ")
+                )))
+            }
+        }
+    }
+
+    fn next_short(&mut self) -> Arg {
+        let c = self.shorts.remove(0);
+        self.option(Arg::Short(c))
+    }
+
+    fn option(&mut self, arg: Arg) -> Arg {
+        self.last = Some(arg.clone());
+        arg
+    }
+
+    fn fail(&self, message: &str) -> i32 {
+        eprintln!("{}: {}", self.command, message);
+        eprintln!("Try '{} --help' for more information.", self.command);
+        1
+    }
+}
+
+/// Answers `--help` and `--version` for built-ins that do not use
+/// [`Parser`] because their arguments are not GNU-style options.
+///
+/// Like GNU `echo` and `printf`, these are only recognized as the sole
+/// argument. Returns the exit status if one was answered.
+pub fn help_or_version(command: &str, usage: &str, args: &[String]) -> Option<i32> {
+    match args {
+        [arg] if arg == "--help" => print!("{}", usage),
+        [arg] if arg == "--version" => {
+            println!("{} (kinda_shell) {}", command, env!("CARGO_PKG_VERSION"))
+        }
+        _ => return None,
+    }
+    Some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALIASES: &[(&str, char)] = &[("all", 'a'), ("almost-all", 'A'), ("ignore", 'I')];
+    const LONGS: &[&str] = &["color", "format", "hide"];
+
+    fn parser(args: &[&str]) -> Parser {
+        let args = args.iter().map(|s| s.to_string()).collect();
+        Parser::new("test", "", args).aliases(ALIASES).longs(LONGS)
+    }
+
+    fn short(c: char) -> Option<Arg> {
+        Some(Arg::Short(c))
+    }
+
+    fn long(name: &str) -> Option<Arg> {
+        Some(Arg::Long(name.to_string()))
+    }
+
+    fn operand(text: &str) -> Option<Arg> {
+        Some(Arg::Operand(text.to_string()))
+    }
+
+    #[test]
+    fn splits_bundled_shorts() {
+        let mut p = parser(&["-la", "x"]);
+        assert_eq!(p.next(), Ok(short('l')));
+        assert_eq!(p.next(), Ok(short('a')));
+        assert_eq!(p.next(), Ok(operand("x")));
+        assert_eq!(p.next(), Ok(None));
+    }
+
+    #[test]
+    fn takes_short_values_attached_or_separate() {
+        let mut p = parser(&["-Ipat", "-I", "other", "-lIrest"]);
+        assert_eq!(p.next(), Ok(short('I')));
+        assert_eq!(p.value(), Ok("pat".to_string()));
+        assert_eq!(p.next(), Ok(short('I')));
+        assert_eq!(p.value(), Ok("other".to_string()));
+        assert_eq!(p.next(), Ok(short('l')));
+        assert_eq!(p.next(), Ok(short('I')));
+        assert_eq!(p.value(), Ok("rest".to_string()));
+        assert_eq!(p.next(), Ok(None));
+    }
+
+    #[test]
+    fn takes_long_values_after_equals_or_separate() {
+        let mut p = parser(&["--format=long", "--format", "json", "--hide="]);
+        assert_eq!(p.next(), Ok(long("format")));
+        assert_eq!(p.value(), Ok("long".to_string()));
+        assert_eq!(p.next(), Ok(long("format")));
+        assert_eq!(p.value(), Ok("json".to_string()));
+        assert_eq!(p.next(), Ok(long("hide")));
+        assert_eq!(p.value(), Ok(String::new()));
+        assert_eq!(p.next(), Ok(None));
+    }
+
+    #[test]
+    fn reports_missing_values() {
+        let mut p = parser(&["-I"]);
+        assert_eq!(p.next(), Ok(short('I')));
+        assert_eq!(p.value(), Err(1));
+        let mut p = parser(&["--format"]);
+        assert_eq!(p.next(), Ok(long("format")));
+        assert_eq!(p.value(), Err(1));
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let mut p = parser(&["-a", "--", "-l", "--all", "--"]);
+        assert_eq!(p.next(), Ok(short('a')));
+        assert_eq!(p.next(), Ok(operand("-l")));
+        assert_eq!(p.next(), Ok(operand("--all")));
+        assert_eq!(p.next(), Ok(operand("--")));
+        assert_eq!(p.next(), Ok(None));
+    }
+
+    #[test]
+    fn single_dash_is_an_operand() {
+        let mut p = parser(&["-", "-a"]);
+        assert_eq!(p.next(), Ok(operand("-")));
+        assert_eq!(p.next(), Ok(short('a')));
+    }
+
+    #[test]
+    fn resolves_aliases_and_prefixes() {
+        let mut p = parser(&["--all", "--alm", "--ig=x", "--col", "--f", "j", "--nope"]);
+        assert_eq!(p.next(), Ok(short('a')));
+        assert_eq!(p.next(), Ok(short('A')));
+        assert_eq!(p.next(), Ok(short('I')));
+        assert_eq!(p.value(), Ok("x".to_string()));
+        assert_eq!(p.next(), Ok(long("color")));
+        assert_eq!(p.next(), Ok(long("format")));
+        assert_eq!(p.value(), Ok("j".to_string()));
+        assert_eq!(p.next(), Ok(long("nope")));
+    }
+
+    #[test]
+    fn rejects_ambiguous_prefixes() {
+        // `--al` could be `--all` or `--almost-all`; `--h` could be `--hide`
+        // or `--help`.
+        assert_eq!(parser(&["--al"]).next(), Err(1));
+        assert_eq!(parser(&["--h"]).next(), Err(1));
+        assert_eq!(parser(&["--all"]).next(), Ok(short('a')));
+    }
+
+    #[test]
+    fn optional_value_only_comes_after_equals() {
+        let mut p = parser(&["--color=never", "--color", "always"]);
+        assert_eq!(p.next(), Ok(long("color")));
+        assert_eq!(p.optional_value(), Some("never".to_string()));
+        assert_eq!(p.next(), Ok(long("color")));
+        assert_eq!(p.optional_value(), None);
+        assert_eq!(p.next(), Ok(operand("always")));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let p = parser(&[]);
+        assert_eq!(p.unexpected(Arg::Short('z')), 1);
+        assert_eq!(p.unexpected(Arg::Long("nope".to_string())), 1);
+        assert_eq!(p.unexpected(Arg::Operand("extra".to_string())), 1);
+
+        // A flag given a value fails on the following call.
+        let mut p = parser(&["--all=x", "y"]);
+        assert_eq!(p.next(), Ok(short('a')));
+        assert_eq!(p.next(), Err(1));
+    }
+
+    #[test]
+    fn answers_help_and_version() {
+        assert_eq!(parser(&["--help"]).next(), Err(0));
+        assert_eq!(parser(&["--vers"]).next(), Err(0));
+        assert_eq!(parser(&["--", "--help"]).next(), Ok(operand("--help")));
+
+        let args =
+            |items: &[&str]| -> Vec<String> { items.iter().map(|s| s.to_string()).collect() };
+        assert_eq!(help_or_version("test", "", &args(&["--help"])), Some(0));
+        assert_eq!(help_or_version("test", "", &args(&["--version"])), Some(0));
+        assert_eq!(help_or_version("test", "", &args(&["--help", "x"])), None);
+        assert_eq!(help_or_version("test", "", &args(&["x"])), None);
+    }
+}
@@ -0,0 +1,739 @@
+use super::echo::unescape;
+use super::options::help_or_version;
+use std::env;
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Flags, width and precision of one `%` conversion.
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+/// Largest accepted width or precision. The output is built in memory, so
+/// a huge one would exhaust it.
+const MAX_FIELD: usize = 1 << 20;
+
+/// Arguments left to consume, plus whether any were used in this pass.
+struct Args<'a> {
+    items: std::slice::Iter<'a, String>,
+    used: bool,
+    status: i32,
+}
+
+impl Args<'_> {
+    fn next_str(&mut self) -> Option<&str> {
+        let item = self.items.next();
+        self.used |= item.is_some();
+        item.map(String::as_str)
+    }
+
+    fn next_int(&mut self) -> i128 {
+        match self.next_str() {
+            None => 0,
+            Some(s) => match parse_int(s) {
+                Some(n) => n,
+                None => {
+                    eprintln!("printf: '{}': invalid number", s);
+                    self.status = 1;
+                    0
+                }
+            },
+        }
+    }
+
+    fn next_float(&mut self) -> f64 {
+        match self.next_str() {
+            None => 0.0,
+            Some(s) => match s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .or_else(|| parse_int(s).map(|n| n as f64))
+            {
+                Some(f) => f,
+                None => {
+                    eprintln!("printf: '{}': invalid number", s);
+                    self.status = 1;
+                    0.0
+                }
+            },
+        }
+    }
+}
+
+const USAGE: &str = "\
+Usage: printf [-v VAR] FORMAT [ARGUMENT]...
+Print ARGUMENT(s) according to FORMAT.
+
+  -v VAR    assign the output to shell variable VAR instead of printing it
+";
+
+/// Formats and prints its arguments under control of a format string.
+///
+/// # Arguments
+///
+/// * `args` - `[-v VAR] FORMAT [ARGUMENTS...]`.
+///
+/// # Behavior
+///
+/// - Supports `%s %d %i %u %o %x %X %f %F %e %E %g %G %c %b %q %%`, the
+///   flags `- + # 0` and space, and width and precision, either literal or
+///   `*` to take them from the next argument.
+/// - `%b` expands backslash escapes in its argument like `echo -e`; `%q`
+///   quotes it for reuse as shell input.
+/// - The format is reused while arguments remain; missing arguments count
+///   as empty strings or zero.
+/// - With `-v VAR`, the output is stored in the variable `VAR` instead of
+///   being printed. `VAR` must be a valid name and the output must not
+///   contain a NUL byte.
+/// - A width or precision above 1048576 is an error and nothing is printed.
+///
+/// # Example
+///
+/// ```
+/// printf(vec!["%-5s|%03d\n".to_string(), "ab".to_string(), "7".to_string()]);
+/// printf(vec!["-v".to_string(), "x".to_string(), "%x".to_string(), "255".to_string()]);
+/// ```
+pub fn printf(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("printf", USAGE, &args) {
+        return status;
+    }
+    let mut rest = &args[..];
+    let mut var = None;
+    if rest.first().is_some_and(|a| a == "-v") {
+        match rest.get(1) {
+            Some(name) => var = Some(name.clone()),
+            None => {
+                eprintln!("printf: -v: option requires an argument");
+                return 2;
+            }
+        }
+        rest = &rest[2..];
+    }
+    if let Some(name) = &var {
+        if !is_name(name) {
+            eprintln!("printf: '{}': not a valid identifier", name);
+            return 1;
+        }
+    }
+    if rest.first().is_some_and(|a| a == "--") {
+        rest = &rest[1..];
+    }
+    let Some((format, operands)) = rest.split_first() else {
+        eprintln!("printf: usage: printf [-v var] format [arguments]");
+        return 2;
+    };
+
+    let (out, status) = match format_all(format, operands) {
+        Ok(result) => result,
+        Err(message) => {
+            eprintln!("printf: {}", message);
+            return 1;
+        }
+    };
+
+    match var {
+        Some(name) if out.contains(&0) => {
+            eprintln!("printf: {}: cannot store a NUL byte in a variable", name);
+            return 1;
+        }
+        Some(name) => env::set_var(name, String::from_utf8_lossy(&out).into_owned()),
+        None => {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            if let Err(e) = handle.write_all(&out).and_then(|_| handle.flush()) {
+                eprintln!("printf: write error: {}", e);
+                return 1;
+            }
+        }
+    }
+    status
+}
+
+/// Renders `format`, reused while operands remain. Returns the output and
+/// the exit status, which is `1` if an operand was not a valid number.
+fn format_all(format: &str, operands: &[String]) -> Result<(Vec<u8>, i32), String> {
+    let mut args = Args {
+        items: operands.iter(),
+        used: false,
+        status: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        args.used = false;
+        if !render(format, &mut args, &mut out)? {
+            break;
+        }
+        if !args.used || args.items.len() == 0 {
+            break;
+        }
+    }
+    Ok((out, args.status))
+}
+
+/// Renders `format` once into `out`. Returns `false` if `\c` in a `%b`
+/// argument asked to stop all output, or an error that must stop `printf`.
+fn render(format: &str, args: &mut Args, out: &mut Vec<u8>) -> Result<bool, String> {
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => format_escape(&mut chars, out),
+            '%' => {
+                if chars.peek() == Some(&'%') {
+                    chars.next();
+                    out.push(b'%');
+                    continue;
+                }
+                let spec = parse_spec(&mut chars, args)?;
+                let Some(conv) = chars.next() else {
+                    eprintln!("printf: %: missing format character");
+                    args.status = 1;
+                    return Ok(true);
+                };
+                if !convert(conv, &spec, args, out) {
+                    return Ok(false);
+                }
+            }
+            _ => {
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    Ok(true)
+}
+
+fn parse_spec(chars: &mut Peekable<Chars>, args: &mut Args) -> Result<Spec, String> {
+    let mut spec = Spec::default();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '-' => spec.left = true,
+            '+' => spec.plus = true,
+            ' ' => spec.space = true,
+            '#' => spec.alt = true,
+            '0' => spec.zero = true,
+            _ => break,
+        }
+        chars.next();
+    }
+
+    if chars.peek() == Some(&'*') {
+        chars.next();
+        let w = args.next_int();
+        if w < 0 {
+            spec.left = true;
+        }
+        let size = usize::try_from(w.unsigned_abs()).ok();
+        spec.width = Some(field(size, &w.to_string(), "field width")?);
+    } else {
+        spec.width = take_number(chars, "field width")?;
+    }
+
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        if chars.peek() == Some(&'*') {
+            chars.next();
+            let p = args.next_int();
+            spec.precision = match usize::try_from(p) {
+                Ok(size) => Some(field(Some(size), &p.to_string(), "precision")?),
+                // A negative precision counts as none; a huge one is rejected.
+                Err(_) if p < 0 => None,
+                Err(_) => return Err(format!("invalid precision: '{}'", p)),
+            };
+        } else {
+            spec.precision = Some(take_number(chars, "precision")?.unwrap_or(0));
+        }
+    }
+    Ok(spec)
+}
+
+/// Checks a width or precision written as `text`, whose value is `size`
+/// unless it overflowed.
+fn field(size: Option<usize>, text: &str, what: &str) -> Result<usize, String> {
+    size.filter(|&n| n <= MAX_FIELD)
+        .ok_or_else(|| format!("invalid {}: '{}'", what, text))
+}
+
+/// Applies one conversion. Returns `false` if output must stop (`%b` with `\c`).
+fn convert(conv: char, spec: &Spec, args: &mut Args, out: &mut Vec<u8>) -> bool {
+    match conv {
+        's' => {
+            let s = args.next_str().unwrap_or_default();
+            let s: String = match spec.precision {
+                Some(p) => s.chars().take(p).collect(),
+                None => s.to_string(),
+            };
+            pad(out, spec, "", &s, false);
+        }
+        'b' => {
+            let mut bytes = Vec::new();
+            let keep_going = unescape(args.next_str().unwrap_or_default(), &mut bytes);
+            // Written as bytes, since octal and hex escapes need not be UTF-8.
+            let len = String::from_utf8_lossy(&bytes).chars().count();
+            let fill = " ".repeat(spec.width.unwrap_or(0).saturating_sub(len));
+            if spec.left {
+                out.extend_from_slice(&bytes);
+                out.extend_from_slice(fill.as_bytes());
+            } else {
+                out.extend_from_slice(fill.as_bytes());
+                out.extend_from_slice(&bytes);
+            }
+            if !keep_going {
+                return false;
+            }
+        }
+        'q' => pad(
+            out,
+            spec,
+            "",
+            &quote(args.next_str().unwrap_or_default()),
+            false,
+        ),
+        'c' => {
+            let s = args.next_str().unwrap_or_default();
+            pad(
+                out,
+                spec,
+                "",
+                &s.chars().next().map(String::from).unwrap_or_default(),
+                false,
+            );
+        }
+        'd' | 'i' => {
+            let n = args.next_int();
+            let sign = if n < 0 {
+                "-"
+            } else if spec.plus {
+                "+"
+            } else if spec.space {
+                " "
+            } else {
+                ""
+            };
+            let digits = min_digits(n.unsigned_abs().to_string(), spec.precision);
+            pad(out, spec, sign, &digits, true);
+        }
+        'u' | 'o' | 'x' | 'X' => {
+            let n = args.next_int();
+            let n = if n < 0 {
+                n as i64 as u64 as u128
+            } else {
+                n as u128
+            };
+            let (digits, prefix) = match conv {
+                'u' => (n.to_string(), ""),
+                'o' => (
+                    format!("{:o}", n),
+                    if spec.alt && n != 0 { "0" } else { "" },
+                ),
+                'x' => (
+                    format!("{:x}", n),
+                    if spec.alt && n != 0 { "0x" } else { "" },
+                ),
+                _ => (
+                    format!("{:X}", n),
+                    if spec.alt && n != 0 { "0X" } else { "" },
+                ),
+            };
+            pad(out, spec, prefix, &min_digits(digits, spec.precision), true);
+        }
+        'f' | 'F' | 'e' | 'E' | 'g' | 'G' => {
+            let f = args.next_float();
+            let sign = if f.is_sign_negative() && f != 0.0 {
+                "-"
+            } else if spec.plus {
+                "+"
+            } else if spec.space {
+                " "
+            } else {
+                ""
+            };
+            let body = format_float(f.abs(), conv, spec.precision.unwrap_or(6), spec.alt);
+            pad(out, spec, sign, &body, f.is_finite());
+        }
+        other => {
+            eprintln!("printf: %{}: invalid format character", other);
+            args.status = 1;
+        }
+    }
+    true
+}
+
+/// Writes `prefix` and `body` padded to the spec's width. Zero padding
+/// goes between the two and only applies to numbers.
+fn pad(out: &mut Vec<u8>, spec: &Spec, prefix: &str, body: &str, numeric: bool) {
+    let len = prefix.chars().count() + body.chars().count();
+    let fill = spec.width.unwrap_or(0).saturating_sub(len);
+    let zero = numeric && spec.zero && !spec.left && spec.precision.is_none();
+    let text = if spec.left {
+        format!("{}{}{}", prefix, body, " ".repeat(fill))
+    } else if zero {
+        format!("{}{}{}", prefix, "0".repeat(fill), body)
+    } else {
+        format!("{}{}{}", " ".repeat(fill), prefix, body)
+    };
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn min_digits(digits: String, precision: Option<usize>) -> String {
+    match precision {
+        Some(0) if digits == "0" => String::new(),
+        Some(p) if digits.len() < p => format!("{}{}", "0".repeat(p - digits.len()), digits),
+        _ => digits,
+    }
+}
+
+/// Formats a non-negative float the way C's `printf` does.
+fn format_float(f: f64, conv: char, precision: usize, alt: bool) -> String {
+    if !f.is_finite() {
+        let s = if f.is_nan() { "nan" } else { "inf" };
+        return if conv.is_ascii_uppercase() {
+            s.to_uppercase()
+        } else {
+            s.to_string()
+        };
+    }
+    let s = match conv.to_ascii_lowercase() {
+        'f' => format!("{:.*}", precision, f),
+        'e' => exponent(f, precision),
+        _ => {
+            let p = precision.max(1);
+            let exp = if f == 0.0 {
+                0
+            } else {
+                exponent(f, p - 1)
+                    .rsplit('e')
+                    .next()
+                    .and_then(|e| e.parse::<i32>().ok())
+                    .unwrap_or(0)
+            };
+            let s = if exp < -4 || exp >= p as i32 {
+                exponent(f, p - 1)
+            } else {
+                format!("{:.*}", (p as i32 - 1 - exp) as usize, f)
+            };
+            if alt {
+                s
+            } else {
+                strip_zeros(&s)
+            }
+        }
+    };
+    let s = if alt && !s.contains('.') {
+        match s.find('e') {
+            Some(i) => format!("{}.{}", &s[..i], &s[i..]),
+            None => format!("{}.", s),
+        }
+    } else {
+        s
+    };
+    if conv.is_ascii_uppercase() {
+        s.to_uppercase()
+    } else {
+        s
+    }
+}
+
+/// `%e` notation: mantissa with `precision` decimals, then `e±NN`.
+fn exponent(f: f64, precision: usize) -> String {
+    let s = format!("{:.*e}", precision, f);
+    match s.split_once('e') {
+        Some((mantissa, exp)) => {
+            let exp: i32 = exp.parse().unwrap_or(0);
+            let sign = if exp < 0 { '-' } else { '+' };
+            format!("{}e{}{:02}", mantissa, sign, exp.abs())
+        }
+        None => s,
+    }
+}
+
+/// Removes trailing fractional zeros (and a bare `.`) for `%g`.
+fn strip_zeros(s: &str) -> String {
+    let (mantissa, exp) = match s.find('e') {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    format!("{}{}", mantissa, exp)
+}
+
+/// Quotes `s` so that the shell reads it back as a single word.
+fn quote(s: &str) -> String {
+    if s.is_empty() {
+        return "''".to_string();
+    }
+    if s.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./-_".contains(c))
+    {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Parses an integer argument: decimal, `0x` hex, `0` octal, or `'c` for
+/// the code point of `c`.
+fn parse_int(s: &str) -> Option<i128> {
+    let t = s.trim();
+    if let Some(c) = t.strip_prefix(['\'', '"']) {
+        return Some(c.chars().next().map_or(0, |c| c as i128));
+    }
+    let (neg, digits) = match t.strip_prefix('-') {
+        Some(d) => (true, d),
+        None => (false, t.strip_prefix('+').unwrap_or(t)),
+    };
+    let n = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        i128::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i128::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if neg { -n } else { n })
+}
+
+/// Reads a literal width or precision, if there is one.
+fn take_number(chars: &mut Peekable<Chars>, what: &str) -> Result<Option<usize>, String> {
+    let mut text = String::new();
+    let mut n = Some(0usize);
+    while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+        text.push(chars.next().unwrap_or_default());
+        n = n
+            .and_then(|n| n.checked_mul(10))
+            .and_then(|n| n.checked_add(d as usize));
+    }
+    if text.is_empty() {
+        return Ok(None);
+    }
+    field(n, &text, what).map(Some)
+}
+
+/// Whether `name` can be a variable name: a letter or `_`, then letters,
+/// digits and `_`.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Handles a backslash escape in the format string itself, where octal
+/// escapes are `\NNN` (not `\0NNN` as in `%b`).
+fn format_escape(chars: &mut Peekable<Chars>, out: &mut Vec<u8>) {
+    match chars.peek() {
+        Some(d) if d.is_digit(8) => {
+            let mut value = 0u32;
+            for _ in 0..3 {
+                match chars.peek().and_then(|c| c.to_digit(8)) {
+                    Some(d) => {
+                        value = value * 8 + d;
+                        chars.next();
+                    }
+                    None => break,
+                }
+            }
+            out.push(value as u8);
+        }
+        Some('"') => {
+            chars.next();
+            out.push(b'"');
+        }
+        Some('\'') => {
+            chars.next();
+            out.push(b'\'');
+        }
+        Some('?') => {
+            chars.next();
+            out.push(b'?');
+        }
+        Some(_) => {
+            let escape: String = ['\\', chars.next().unwrap_or_default()].iter().collect();
+            let mut tail = String::new();
+            // \x, \u and \U take their digits from the following characters.
+            if escape.ends_with(['x', 'u', 'U']) {
+                let max = match escape.chars().last() {
+                    Some('x') => 2,
+                    Some('u') => 4,
+                    _ => 8,
+                };
+                while tail.len() < max && chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    tail.push(chars.next().unwrap_or_default());
+                }
+            }
+            // `\c` has no special meaning in a format string.
+            if escape == "\\c" {
+                out.extend_from_slice(b"\\c");
+            } else {
+                unescape(&format!("{}{}", escape, tail), out);
+            }
+        }
+        None => out.push(b'\\'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Renders `format` with `operands`, returning the output and status.
+    fn fmt(format: &str, operands: &[&str]) -> (String, i32) {
+        let (out, status) = format_all(format, &strings(operands)).unwrap();
+        (String::from_utf8(out).unwrap(), status)
+    }
+
+    fn text(format: &str, operands: &[&str]) -> String {
+        fmt(format, operands).0
+    }
+
+    #[test]
+    fn strings_and_characters() {
+        assert_eq!(
+            text("[%s|%5s|%-5s|%.2s]", &["abc", "ab", "ab", "abc"]),
+            "[abc|   ab|ab   |ab]"
+        );
+        assert_eq!(text("[%c|%c|%3c]", &["abc", "é", "x"]), "[a|é|  x]");
+        assert_eq!(text("[%s|%d]", &[]), "[|0]");
+    }
+
+    #[test]
+    fn signed_integers() {
+        assert_eq!(
+            text(
+                "[%d|%i|%+d|% d|%05d|%-5d|%.3d|%5.3d]",
+                &["42", "-7", "5", "5", "-42", "42", "7", "7"]
+            ),
+            "[42|-7|+5| 5|-0042|42   |007|  007]"
+        );
+        assert_eq!(
+            text("[%d|%d|%d|%.0d]", &["0x1f", "010", "'A", "0"]),
+            "[31|8|65|]"
+        );
+    }
+
+    #[test]
+    fn unsigned_integers() {
+        assert_eq!(
+            text(
+                "[%u|%o|%#o|%x|%#x|%X|%#X|%#o]",
+                &["42", "8", "8", "255", "255", "255", "255", "0"]
+            ),
+            "[42|10|010|ff|0xff|FF|0XFF|0]"
+        );
+        assert_eq!(text("%u", &["-1"]), "18446744073709551615");
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(
+            text(
+                "[%f|%.2f|%8.3f|%-8.1f|%+.1f|%F]",
+                &["3.14159", "2.5", "1", "1", "1", "1"]
+            ),
+            "[3.141590|2.50|   1.000|1.0     |+1.0|1.000000]"
+        );
+        assert_eq!(
+            text(
+                "[%e|%.2E|%g|%G|%g|%#g|%g]",
+                &[
+                    "12345.678",
+                    "0.000123",
+                    "0.0001",
+                    "1e-5",
+                    "100000",
+                    "1",
+                    "1234567"
+                ]
+            ),
+            "[1.234568e+04|1.23E-04|0.0001|1E-05|100000|1.00000|1.23457e+06]"
+        );
+        assert_eq!(text("[%f|%E]", &["inf", "nan"]), "[inf|NAN]");
+    }
+
+    #[test]
+    fn escapes_and_quoting() {
+        assert_eq!(text("[%b|%b]", &["a\\tb", "\\0101"]), "[a\tb|A]");
+        assert_eq!(
+            text("[%q|%q|%q]", &["it's", "", "simple"]),
+            "['it'\\''s'|''|simple]"
+        );
+        assert_eq!(text("%%\\101\\n", &[]), "%A\n");
+        // `\c` in a `%b` argument stops all output, including format reuse.
+        assert_eq!(text("%b-", &["a\\cb", "c"]), "a");
+    }
+
+    #[test]
+    fn star_width_and_precision() {
+        assert_eq!(
+            text("[%*d|%-*d|%.*f]", &["4", "7", "4", "7", "1", "3.14159"]),
+            "[   7|7   |3.1]"
+        );
+        // A negative width left-aligns; a negative precision is ignored.
+        assert_eq!(
+            text("[%*d|%.*f]", &["-3", "1", "-1", "2.5"]),
+            "[1  |2.500000]"
+        );
+    }
+
+    #[test]
+    fn reuses_format_while_operands_remain() {
+        assert_eq!(
+            text("<%s=%d>", &["a", "1", "b", "2", "c"]),
+            "<a=1><b=2><c=0>"
+        );
+        assert_eq!(text("x\n", &["unused"]), "x\n");
+    }
+
+    #[test]
+    fn invalid_numbers_set_status() {
+        assert_eq!(fmt("%d|%f", &["abc", "x"]), ("0|0.000000".to_string(), 1));
+        assert_eq!(fmt("%y", &[]).1, 1);
+    }
+
+    #[test]
+    fn rejects_huge_widths_and_precisions() {
+        for format in ["%99999999999999999999d", "%2000000d", "%.99999999999f"] {
+            assert!(format_all(format, &strings(&["1"])).is_err(), "{}", format);
+        }
+        assert!(format_all("%*d", &strings(&["9999999999999999", "1"])).is_err());
+        assert!(format_all("%.*d", &strings(&["99999999999999999999", "1"])).is_err());
+        assert_eq!(text("%1048576s", &["x"]).len(), MAX_FIELD);
+        assert_eq!(printf(strings(&["%99999999999999999999d", "1"])), 1);
+    }
+
+    #[test]
+    fn assigns_to_variable() {
+        assert_eq!(
+            printf(strings(&["-v", "PRINTF_TEST_VAR", "%s-%03d", "a", "7"])),
+            0
+        );
+        assert_eq!(env::var("PRINTF_TEST_VAR").unwrap(), "a-007");
+    }
+
+    #[test]
+    fn rejects_bad_variables() {
+        assert_eq!(printf(strings(&["-v", "a=b", "hi"])), 1);
+        assert_eq!(printf(strings(&["-v", "1x", "hi"])), 1);
+        assert_eq!(printf(strings(&["-v", "PRINTF_TEST_NUL", "%b", "\\0"])), 1);
+        assert!(env::var_os("PRINTF_TEST_NUL").is_none());
+        assert_eq!(printf(strings(&["-v"])), 2);
+    }
+}
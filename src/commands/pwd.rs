@@ -1,9 +1,75 @@
+use super::options::{Arg, Parser};
+use std::env;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+const USAGE: &str = "\
+Usage: pwd [-L|-P]
+Print the name of the current working directory.
+
+  -L        print the value of $PWD if it names the current directory
+  -P        print the directory with all symbolic links resolved
+  --help    display this help and exit
+";
+
 /// Prints the current working directory.
 ///
 /// # Arguments
 ///
-/// * `cdir` - Reference to a string representing the current directory path.
+/// * `args` - Vector of options:
+///   - `-L`: print the logical path kept in `$PWD` (default).
+///   - `-P`: print the physical path with all symlinks resolved.
+///
+/// # Example
+///
+/// ```
+/// pwd(vec![]);
+/// pwd(vec!["-P".to_string()]);
+/// ```
+pub fn pwd(args: Vec<String>) -> i32 {
+    let mut physical = false;
+    let mut parser = Parser::new("pwd", USAGE, args);
+    loop {
+        match parser.next() {
+            Ok(Some(Arg::Short('L'))) => physical = false,
+            Ok(Some(Arg::Short('P'))) => physical = true,
+            Ok(Some(arg)) => return parser.unexpected(arg),
+            Ok(None) => break,
+            Err(status) => return status,
+        }
+    }
+
+    if physical {
+        match env::current_dir().and_then(fs::canonicalize) {
+            Ok(p) => println!("{}", p.display()),
+            Err(e) => {
+                eprintln!("pwd: {}", e);
+                return 1;
+            }
+        }
+    } else {
+        println!("{}", logical());
+    }
+    0
+}
+
+/// Returns the logical current directory.
+///
+/// This is `$PWD` when it is an absolute path that still refers to the
+/// process working directory, and the physical directory otherwise.
+pub fn logical() -> String {
+    let physical = env::current_dir()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+    match env::var("PWD") {
+        Ok(pwd) if Path::new(&pwd).is_absolute() && same_file(&pwd, ".") => pwd,
+        _ => physical,
+    }
+}
 
-pub fn pwd(cdir: &String) {
-    println!("{}", cdir);
+fn same_file(a: &str, b: &str) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
 }
@@ -1,67 +1,362 @@
-use std::fs;
+use super::options::{Arg, Parser};
+use super::strerror;
+use super::{confirm, trash};
+use std::fs::{self, Metadata};
+use std::io;
+use std::os::unix::fs::FileTypeExt;
 use std::path::Path;
+
+/// When `rm` asks for confirmation.
+#[derive(Clone, Copy, PartialEq)]
+enum Prompt {
+    Never,
+    /// Once before starting, for more than three operands or recursion (`-I`).
+    Once,
+    /// Before every removal (`-i`).
+    Always,
+}
+
+struct RmOptions {
+    force: bool,
+    recursive: bool,
+    dir: bool,
+    verbose: bool,
+    prompt: Prompt,
+    preserve_root: bool,
+    trash: bool,
+}
+
+const USAGE: &str = "\
+Usage: rm [OPTION]... [FILE]...
+Remove (unlink) the FILE(s).
+
+  -f, --force           ignore nonexistent files and arguments, never prompt
+  -i                    prompt before every removal
+  -I                    prompt once before removing more than three files,
+                        or when removing recursively
+      --interactive[=WHEN]  prompt according to WHEN: never, once (-I), or
+                        always (-i); without WHEN, prompt always
+      --no-preserve-root  do not treat '/' specially
+      --preserve-root   do not remove '/' (default)
+  -r, -R, --recursive   remove directories and their contents recursively
+  -d, --dir             remove empty directories
+  -v, --verbose         explain what is being done
+      --trash           move files to the trash instead ($RM_TRASH)
+      --no-trash        delete files even when $RM_TRASH is set
+      --help            display this help and exit
+      --version         output version information and exit
+";
+
+const LONG_ALIASES: &[(&str, char)] = &[
+    ("force", 'f'),
+    ("recursive", 'r'),
+    ("dir", 'd'),
+    ("verbose", 'v'),
+];
+
+const LONG_OPTIONS: &[&str] = &[
+    "interactive",
+    "preserve-root",
+    "no-preserve-root",
+    "trash",
+    "no-trash",
+];
+
 /// Removes files or directories specified in the arguments.
 ///
-/// Supports the `-r` flag for r_dir removal of directories.
-///
 /// # Arguments
 ///
 /// * `args` - Vector of strings representing command-line arguments,
-///            e.g. `["-r", "folder", "file.txt"]`
+///   e.g. `["-rf", "folder", "file.txt"]`
 ///
-/// # Behavior
+/// # Options
 ///
-/// - If an unknown flag is found (anything other than `-r`), prints an error and exits.
-/// - If no paths are provided, prints a missing operand error.
-/// - Deletes files directly.
-/// - Deletes directories only if `-r` flag is present; otherwise, prints an error.
+/// - `-r`, `-R`, `--recursive`: remove directories and their contents.
+/// - `-d`, `--dir`: remove empty directories.
+/// - `-f`, `--force`: ignore missing files and never prompt.
+/// - `-i`: prompt before every removal; `-I`: prompt once before removing
+///   more than three files or removing recursively.
+/// - `-v`, `--verbose`: report each removed path.
+/// - `--no-preserve-root`: allow removing `/`, `.` and `..`.
+/// - `--trash`: move paths to the trash instead of deleting them; this is
+///   the default when `$RM_TRASH` is set, and `--no-trash` turns it off.
+/// - `--`: treat every following argument as a path.
+/// - `--help`, `--version`: print usage or version information.
+///
+/// # Returns
+///
+/// `0` if every path was removed, `1` otherwise.
 ///
 /// # Example
 ///
 /// ```
-/// rm(vec!["-r".to_string(), "mydir".to_string()]);
-/// rm(vec!["file.txt".to_string()]);
+/// rm(vec!["-rf".to_string(), "mydir".to_string()]);
+/// rm(vec!["--".to_string(), "-file.txt".to_string()]);
 /// ```
-pub fn rm(args: Vec<String>) {
-    let mut r_dir = false;
-    let mut paths = Vec::new();
+pub fn rm(args: Vec<String>) -> i32 {
+    let (opts, paths) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(status) => return status,
+    };
 
-    for arg in &args {
-        let arg = arg.as_str();
-        if arg == "-r"  {
-            r_dir = true;
-        } else if !arg.starts_with('-') {
-            paths.push(arg);
-        } else {
-            eprintln!("rm: invalid option -- '{}'", arg.chars().nth(1).unwrap_or(' '));
-            return;
-        }
-    }
-    
     if paths.is_empty() {
+        if opts.force {
+            return 0;
+        }
         eprintln!("rm: missing operand");
-        return;
+        return 1;
     }
 
-    for path_str in paths {
-        let path = Path::new(path_str);
-        if !path.exists() {
-            eprintln!("rm: cannot remove '{}': No such file or directory", path_str);
+    if opts.prompt == Prompt::Once && (paths.len() > 3 || opts.recursive) {
+        let plural = if paths.len() == 1 { "" } else { "s" };
+        let how = if opts.recursive { " recursively" } else { "" };
+        if !confirm(&format!("rm: remove {} argument{}{}?", paths.len(), plural, how)) {
+            return 0;
+        }
+    }
+
+    let mut status = 0;
+    for path_str in &paths {
+        let path_str = path_str.as_str();
+        if opts.preserve_root && is_dot_or_dotdot(path_str) {
+            eprintln!(
+                "rm: refusing to remove '.' or '..' directory: skipping '{}'",
+                path_str
+            );
+            status = 1;
+            continue;
+        }
+        if opts.preserve_root && opts.recursive && is_root(path_str) {
+            eprintln!("rm: it is dangerous to operate recursively on '{}'", path_str);
+            eprintln!("rm: use --no-preserve-root to override this failsafe");
+            status = 1;
             continue;
         }
 
-        if path.is_dir() {
-            if r_dir {
-                if let Err(e) = fs::remove_dir_all(path) {
-                    eprintln!("rm: cannot remove '{}': {}", path_str, e);
-                }
-            } else {
-                eprintln!("rm: cannot remove '{}': Is a directory", path_str);
+        let path = Path::new(path_str);
+        let meta = match fs::symlink_metadata(path) {
+            Ok(meta) => meta,
+            // `-f` only ignores files that do not exist.
+            Err(e) if opts.force && e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                eprintln!("rm: cannot remove '{}': {}", path_str, strerror(&e));
+                status = 1;
+                continue;
             }
+        };
+
+        let removed = if opts.trash {
+            move_to_trash(path, &meta, &opts)
         } else {
-            if let Err(e) = fs::remove_file(path) {
-                eprintln!("rm: cannot remove '{}': {}", path_str, e);
+            remove(path, &meta, &opts)
+        };
+        if !removed {
+            status = 1;
+        }
+    }
+    status
+}
+
+fn parse_args(args: Vec<String>) -> Result<(RmOptions, Vec<String>), i32> {
+    let mut opts = RmOptions {
+        force: false,
+        recursive: false,
+        dir: false,
+        verbose: false,
+        prompt: Prompt::Never,
+        preserve_root: true,
+        trash: trash::enabled_by_env(),
+    };
+    let mut paths = Vec::new();
+
+    let mut parser = Parser::new("rm", USAGE, args)
+        .aliases(LONG_ALIASES)
+        .longs(LONG_OPTIONS);
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Arg::Operand(path) => paths.push(path),
+            Arg::Short('r' | 'R') => opts.recursive = true,
+            Arg::Short('d') => opts.dir = true,
+            Arg::Short('v') => opts.verbose = true,
+            Arg::Short(c @ ('f' | 'i' | 'I')) => set_flag(&mut opts, c),
+            Arg::Long(name) => match name.as_str() {
+                "interactive" => match parser.optional_value().as_deref() {
+                    None | Some("always") => set_flag(&mut opts, 'i'),
+                    Some("once") => set_flag(&mut opts, 'I'),
+                    Some("never") => opts.prompt = Prompt::Never,
+                    Some(when) => return Err(parser.invalid_value(when)),
+                },
+                "preserve-root" => opts.preserve_root = true,
+                "no-preserve-root" => opts.preserve_root = false,
+                "trash" => opts.trash = true,
+                "no-trash" => opts.trash = false,
+                _ => return Err(parser.unexpected(Arg::Long(name))),
+            },
+            arg => return Err(parser.unexpected(arg)),
+        }
+    }
+    Ok((opts, paths))
+}
+
+/// Removes `path`, descending into directories when recursive.
+///
+/// Returns `false` if anything could not be removed. A path the user
+/// declines to remove is not a failure.
+fn remove(path: &Path, meta: &Metadata, opts: &RmOptions) -> bool {
+    let shown = path.display();
+
+    if !meta.is_dir() {
+        if opts.prompt == Prompt::Always
+            && !confirm(&format!("rm: remove {} '{}'?", describe(meta), shown))
+        {
+            return true;
+        }
+        return match fs::remove_file(path) {
+            Ok(()) => {
+                if opts.verbose {
+                    println!("removed '{}'", shown);
+                }
+                true
+            }
+            Err(e) => {
+                eprintln!("rm: cannot remove '{}': {}", shown, strerror(&e));
+                false
+            }
+        };
+    }
+
+    if !may_remove_dir(path, opts) {
+        return false;
+    }
+
+    let mut ok = true;
+    if opts.recursive {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("rm: cannot remove '{}': {}", shown, strerror(&e));
+                return false;
+            }
+        };
+        let mut entries = entries.filter_map(Result::ok).peekable();
+        if entries.peek().is_some()
+            && opts.prompt == Prompt::Always
+            && !confirm(&format!("rm: descend into directory '{}'?", shown))
+        {
+            return true;
+        }
+        for entry in entries {
+            let child = entry.path();
+            match fs::symlink_metadata(&child) {
+                Ok(child_meta) => ok &= remove(&child, &child_meta, opts),
+                Err(e) => {
+                    eprintln!("rm: cannot remove '{}': {}", child.display(), strerror(&e));
+                    ok = false;
+                }
+            }
+        }
+    }
+
+    if opts.prompt == Prompt::Always
+        && !confirm(&format!("rm: remove directory '{}'?", shown))
+    {
+        return ok;
+    }
+    match fs::remove_dir(path) {
+        Ok(()) => {
+            if opts.verbose {
+                println!("removed directory '{}'", shown);
+            }
+            ok
+        }
+        Err(e) => {
+            // Children the user chose to keep already explain the failure.
+            if ok {
+                eprintln!("rm: cannot remove '{}': {}", shown, strerror(&e));
+            }
+            false
+        }
+    }
+}
+
+/// Moves `path` to the trash, with the same checks and prompts as [`remove`].
+fn move_to_trash(path: &Path, meta: &Metadata, opts: &RmOptions) -> bool {
+    if meta.is_dir() && !may_remove_dir(path, opts) {
+        return false;
+    }
+    let what = if meta.is_dir() { "directory" } else { describe(meta) };
+    if opts.prompt == Prompt::Always
+        && !confirm(&format!("rm: remove {} '{}'?", what, path.display()))
+    {
+        return true;
+    }
+    match trash::trash_path(path) {
+        Ok(_) => {
+            if opts.verbose {
+                println!("trashed '{}'", path.display());
             }
+            true
         }
+        Err(e) => {
+            eprintln!("rm: cannot move '{}' to trash: {}", path.display(), strerror(&e));
+            false
+        }
+    }
+}
+
+/// Checks that directory `path` may be removed: always with `-r`, and only
+/// when empty with `-d`.
+fn may_remove_dir(path: &Path, opts: &RmOptions) -> bool {
+    if opts.recursive {
+        return true;
+    }
+    let empty = fs::read_dir(path).is_ok_and(|mut d| d.next().is_none());
+    if opts.dir && empty {
+        return true;
     }
+    let reason = if opts.dir {
+        "Directory not empty"
+    } else {
+        "Is a directory"
+    };
+    eprintln!("rm: cannot remove '{}': {}", path.display(), reason);
+    false
+}
+
+/// Applies `-f`, `-i` or `-I`; the last one given wins.
+fn set_flag(opts: &mut RmOptions, flag: char) {
+    opts.force = flag == 'f';
+    opts.prompt = match flag {
+        'i' => Prompt::Always,
+        'I' => Prompt::Once,
+        _ => Prompt::Never,
+    };
+}
+
+fn describe(meta: &Metadata) -> &'static str {
+    let file_type = meta.file_type();
+    if file_type.is_symlink() {
+        "symbolic link"
+    } else if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() {
+        "block special file"
+    } else if file_type.is_char_device() {
+        "character special file"
+    } else if meta.len() == 0 {
+        "regular empty file"
+    } else {
+        "regular file"
+    }
+}
+
+fn is_dot_or_dotdot(path: &str) -> bool {
+    let last = path.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    last == "." || last == ".."
+}
+
+fn is_root(path: &str) -> bool {
+    fs::canonicalize(path).is_ok_and(|p| p == Path::new("/"))
 }
@@ -0,0 +1,357 @@
+use super::mv::move_path;
+use super::options::help_or_version;
+use super::strerror;
+use chrono::{Local, NaiveDateTime};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::{env, path};
+
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// A trashed item as described by its `.trashinfo` file.
+struct Entry {
+    /// Name under `files/` (and `info/` with the `.trashinfo` extension).
+    name: String,
+    original: PathBuf,
+    deleted: NaiveDateTime,
+}
+
+/// Returns `true` if `rm` should trash instead of delete by default, which
+/// is the case when `$RM_TRASH` is set to anything but `0` or the empty string.
+pub fn enabled_by_env() -> bool {
+    env::var("RM_TRASH").is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Moves `path` into the trash, recording where it came from.
+///
+/// Follows the XDG trash layout: the item goes to `Trash/files/NAME` and
+/// its original path and deletion date to `Trash/info/NAME.trashinfo`,
+/// under `$XDG_DATA_HOME` (default `~/.local/share`).
+pub fn trash_path(path: &Path) -> io::Result<PathBuf> {
+    let original = path::absolute(path)?;
+    let (files, info) = trash_dirs()?;
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let base = original
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "root".to_string());
+
+    // Creating the info file with `create_new` reserves the name.
+    let mut n = 1;
+    let (name, mut info_file) = loop {
+        let name = if n == 1 {
+            base.clone()
+        } else {
+            format!("{}_{}", base, n)
+        };
+        let info_path = info.join(format!("{}.trashinfo", name));
+        // Not `exists`, which would take a dangling symlink for a free name.
+        if fs::symlink_metadata(files.join(&name)).is_err() {
+            match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(f) => break (name, f),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+        n += 1;
+    };
+
+    let info_path = info.join(format!("{}.trashinfo", name));
+    let written = write!(
+        info_file,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode(&original.to_string_lossy()),
+        Local::now().format(DATE_FORMAT)
+    );
+    let dest = files.join(&name);
+    if let Err(e) = written.and_then(|_| move_path(path, &dest)) {
+        fs::remove_file(&info_path).ok();
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+const LIST_USAGE: &str = "\
+Usage: trash-list
+List trashed files with the time they were trashed, oldest first.
+";
+
+/// Lists trashed items as `DATE TIME ORIGINAL-PATH`, oldest first.
+///
+/// # Example
+///
+/// ```
+/// trash_list(vec![]);
+/// ```
+pub fn trash_list(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("trash-list", LIST_USAGE, &args) {
+        return status;
+    }
+    if !args.is_empty() {
+        eprintln!("trash-list: too many arguments");
+        return 1;
+    }
+    match entries() {
+        Ok(list) => {
+            for e in list {
+                println!("{} {}", e.deleted.format("%Y-%m-%d %H:%M:%S"), e.original.display());
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("trash-list: {}", strerror(&e));
+            1
+        }
+    }
+}
+
+const RESTORE_USAGE: &str = "\
+Usage: trash-restore PATH...
+Restore trashed files to their original location.
+";
+
+/// Restores trashed items to their original location.
+///
+/// # Arguments
+///
+/// * `args` - Original paths (absolute or relative to the current
+///   directory) or names inside the trash. When a path was trashed more
+///   than once, the most recent copy is restored.
+///
+/// # Behavior
+///
+/// - Refuses to overwrite an existing file at the original path.
+///
+/// # Example
+///
+/// ```
+/// trash_restore(vec!["notes.txt".to_string()]);
+/// ```
+pub fn trash_restore(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("trash-restore", RESTORE_USAGE, &args) {
+        return status;
+    }
+    if args.is_empty() {
+        eprintln!("trash-restore: missing operand");
+        return 1;
+    }
+    let list = match entries() {
+        Ok(list) => list,
+        Err(e) => {
+            eprintln!("trash-restore: {}", strerror(&e));
+            return 1;
+        }
+    };
+    let (files, info) = match trash_dirs() {
+        Ok(dirs) => dirs,
+        Err(e) => {
+            eprintln!("trash-restore: {}", strerror(&e));
+            return 1;
+        }
+    };
+
+    let mut status = 0;
+    for arg in &args {
+        let wanted = path::absolute(arg).unwrap_or_else(|_| PathBuf::from(arg));
+        let found = list
+            .iter()
+            .rev()
+            .find(|e| e.original == wanted)
+            .or_else(|| list.iter().find(|e| &e.name == arg));
+        let Some(entry) = found else {
+            eprintln!("trash-restore: '{}' is not in the trash", arg);
+            status = 1;
+            continue;
+        };
+        if fs::symlink_metadata(&entry.original).is_ok() {
+            eprintln!(
+                "trash-restore: cannot restore '{}': File exists",
+                entry.original.display()
+            );
+            status = 1;
+            continue;
+        }
+        if let Some(parent) = entry.original.parent() {
+            fs::create_dir_all(parent).ok();
+        }
+        match move_path(&files.join(&entry.name), &entry.original) {
+            Ok(()) => {
+                fs::remove_file(info.join(format!("{}.trashinfo", entry.name))).ok();
+            }
+            Err(e) => {
+                eprintln!(
+                    "trash-restore: cannot restore '{}': {}",
+                    entry.original.display(),
+                    strerror(&e)
+                );
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+const EMPTY_USAGE: &str = "\
+Usage: trash-empty [DAYS]
+Permanently delete trashed files, or only those trashed more than DAYS ago.
+";
+
+/// Permanently deletes trashed items.
+///
+/// # Arguments
+///
+/// * `args` - Optionally a number of days; only items trashed longer ago
+///   than that are deleted. With no argument the whole trash is emptied.
+///
+/// # Example
+///
+/// ```
+/// trash_empty(vec!["30".to_string()]);
+/// ```
+pub fn trash_empty(args: Vec<String>) -> i32 {
+    if let Some(status) = help_or_version("trash-empty", EMPTY_USAGE, &args) {
+        return status;
+    }
+    let days = match args.as_slice() {
+        [] => None,
+        [d] => match d.parse::<i64>() {
+            Ok(d) => Some(d),
+            Err(_) => {
+                eprintln!("trash-empty: invalid number of days '{}'", d);
+                return 1;
+            }
+        },
+        _ => {
+            eprintln!("trash-empty: too many arguments");
+            return 1;
+        }
+    };
+    let (files, info) = match trash_dirs() {
+        Ok(dirs) => dirs,
+        Err(e) => {
+            eprintln!("trash-empty: {}", strerror(&e));
+            return 1;
+        }
+    };
+    let list = entries().unwrap_or_default();
+    let now = Local::now().naive_local();
+
+    let mut status = 0;
+    for e in list {
+        if days.is_some_and(|d| now.signed_duration_since(e.deleted).num_days() < d) {
+            continue;
+        }
+        let item = files.join(&e.name);
+        let removed = match fs::symlink_metadata(&item) {
+            Ok(m) if m.is_dir() => fs::remove_dir_all(&item),
+            Ok(_) => fs::remove_file(&item),
+            Err(_) => Ok(()),
+        };
+        match removed {
+            Ok(()) => {
+                fs::remove_file(info.join(format!("{}.trashinfo", e.name))).ok();
+            }
+            Err(err) => {
+                eprintln!(
+                    "trash-empty: cannot remove '{}': {}",
+                    item.display(),
+                    strerror(&err)
+                );
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// Returns the `files` and `info` directories of the home trash.
+fn trash_dirs() -> io::Result<(PathBuf, PathBuf)> {
+    let data_home = match env::var("XDG_DATA_HOME") {
+        Ok(d) if !d.is_empty() => PathBuf::from(d),
+        _ => match env::var("HOME") {
+            Ok(home) => Path::new(&home).join(".local/share"),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "Unable to determine home directory",
+                ))
+            }
+        },
+    };
+    let trash = data_home.join("Trash");
+    Ok((trash.join("files"), trash.join("info")))
+}
+
+/// Reads every `.trashinfo` file, sorted by deletion date.
+fn entries() -> io::Result<Vec<Entry>> {
+    let (_, info) = trash_dirs()?;
+    let dir = match fs::read_dir(&info) {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut list = Vec::new();
+    for entry in dir.filter_map(Result::ok) {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(name) = file_name.strip_suffix(".trashinfo") else {
+            continue;
+        };
+        let Ok(contents) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let mut original = None;
+        let mut deleted = None;
+        for line in contents.lines() {
+            if let Some(p) = line.strip_prefix("Path=") {
+                original = Some(PathBuf::from(decode(p)));
+            } else if let Some(d) = line.strip_prefix("DeletionDate=") {
+                deleted = NaiveDateTime::parse_from_str(d, DATE_FORMAT).ok();
+            }
+        }
+        if let (Some(original), Some(deleted)) = (original, deleted) {
+            list.push(Entry {
+                name: name.to_string(),
+                original,
+                deleted,
+            });
+        }
+    }
+    list.sort_by_key(|e| e.deleted);
+    Ok(list)
+}
+
+/// Percent-encodes a path as required for the `Path=` key.
+fn encode(path: &str) -> String {
+    let mut out = String::new();
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -1,19 +1,16 @@
 mod commands;
 mod parse;
+mod prompt;
 use commands::*;
 use parse::*;
 use std::io::{self, Write};
 fn main() {
-    let mut curr = match std::env::current_dir() {
-        Ok(path) => path.display().to_string(),
-        Err(_) => String::new(),
-    };
+    std::env::set_var("PWD", pwd::logical());
+    let mut status = 0;
     loop {
-        if let Ok(p) = std::env::current_dir() {
-            curr = p.display().to_string();
-        }
+        let curr = pwd::logical();
 
-        print!("$ ");
+        print!("{}", prompt::primary(&curr, status));
         io::stdout().flush().unwrap();
         let mut input = String::new();
         let bytes = io::stdin().read_line(&mut input).unwrap();
@@ -29,7 +26,7 @@ fn main() {
             if let Ok(c) = split(input.trim()) {
                 break c;
             }
-            print!("dequote> ");
+            print!("{}", prompt::secondary(&curr, status));
             io::stdout().flush().unwrap();
             let mut cont = String::new();
             let bytes = io::stdin().read_line(&mut cont).unwrap();
@@ -38,24 +35,32 @@ fn main() {
             }
             input.push_str(&cont);
         };
-        match cmd.command.as_str() {
+        status = match cmd.command.as_str() {
             "exit" => break,
-            "echo" => echo::echo(input.trim()),
-            "pwd" => pwd::pwd(&curr),
+            "echo" => echo::echo(cmd.args),
+            "printf" => printf::printf(cmd.args),
+            "pwd" => pwd::pwd(cmd.args),
             "mkdir" => mkdir::mkdir(cmd.args),
             "cat" => cat::cat(cmd.args),
             "cp" => cp::cp(cmd.args),
             "cd" => cd::cd(cmd.args),
+            "pushd" => dirs::pushd(cmd.args),
+            "popd" => dirs::popd(cmd.args),
+            "dirs" => dirs::dirs(cmd.args),
             "mv" => mv::mv(cmd.args),
             "rm" => rm::rm(cmd.args),
-            "ls" => match ls::ls(cmd.args){
-                Ok(())=>{}
-                Err(e)=>{
-                    println!("{}",e)
-                }
-            },
-            "clear" => clear::clear(cmd.args),
-            _ => println!("Command '{}' not found", cmd.command),
-        }
+            "trash-list" => trash::trash_list(cmd.args),
+            "trash-restore" => trash::trash_restore(cmd.args),
+            "trash-empty" => trash::trash_empty(cmd.args),
+            "ls" => ls::ls(cmd.args),
+            "clear" => {
+                clear::clear(cmd.args);
+                0
+            }
+            _ => {
+                println!("Command '{}' not found", cmd.command);
+                127
+            }
+        };
     }
 }
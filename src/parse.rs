@@ -1,3 +1,4 @@
+use crate::commands::dirs;
 use std::env;
 /// Represents a parsed command from user input.
 #[derive(Debug)]
@@ -26,6 +27,7 @@ pub fn clean_input(input: &str) -> String {
 /// - Single and double quoting
 /// - Environment variable expansion: `$VAR`, `${VAR}`
 /// - Home directory expansion: `~`
+/// - Directory stack expansion: `~+` (`$PWD`), `~-` (`$OLDPWD`), `~N`, `~+N`, `~-N`
 ///
 /// # Errors
 ///
@@ -91,10 +93,24 @@ pub fn split(input: &str) -> Result<Cmd, u8> {
             }
             '~' if current.is_empty() && !in_single_quotes && !in_double_quotes => {
                 chars.next();
-                if let Ok(home) = env::var("HOME") {
-                    current.push_str(&home);
-                } else {
-                    current.push('~');
+                let prefix: String = chars
+                    .clone()
+                    .take_while(|&c| c != '/' && c != ' ')
+                    .collect();
+                let expansion = match prefix.as_str() {
+                    "" => env::var("HOME").ok(),
+                    "+" => env::var("PWD").ok(),
+                    "-" => env::var("OLDPWD").ok(),
+                    n => dirs::entry(n),
+                };
+                match expansion {
+                    Some(dir) => {
+                        current.push_str(&dir);
+                        for _ in prefix.chars() {
+                            chars.next();
+                        }
+                    }
+                    None => current.push('~'),
                 }
             }
             _ => {
@@ -108,7 +124,7 @@ pub fn split(input: &str) -> Result<Cmd, u8> {
         tokens.push(current);
     }
 
-    let command = tokens.get(0).cloned().unwrap_or_default();
+    let command = tokens.first().cloned().unwrap_or_default();
     let args = tokens.iter().skip(1).cloned().collect();
     if in_double_quotes || in_single_quotes {
         return Err(1);
@@ -0,0 +1,219 @@
+use std::env;
+use std::fs;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+use users::{get_current_uid, get_current_username};
+
+/// Prompt printed when `PS1` is not set.
+pub const DEFAULT_PS1: &str = "$ ";
+/// Prompt printed when `PS2` is not set (shown while a quote is left open).
+pub const DEFAULT_PS2: &str = "dequote> ";
+
+/// Returns the primary prompt, built from `PS1`.
+///
+/// # Arguments
+///
+/// * `cwd` - The current working directory, used by `\w` and `\W`.
+/// * `status` - Exit status of the last command, used by `\?`.
+pub fn primary(cwd: &str, status: i32) -> String {
+    let template = env::var("PS1").unwrap_or_else(|_| DEFAULT_PS1.to_string());
+    expand(&template, cwd, status)
+}
+
+/// Returns the continuation prompt, built from `PS2`.
+pub fn secondary(cwd: &str, status: i32) -> String {
+    let template = env::var("PS2").unwrap_or_else(|_| DEFAULT_PS2.to_string());
+    expand(&template, cwd, status)
+}
+
+/// Expands bash-style backslash escapes in a prompt template.
+///
+/// Supported escapes:
+/// - `\u`: user name
+/// - `\h`: host name up to the first `.`, `\H`: full host name
+/// - `\w`: current directory with `$HOME` abbreviated to `~`
+/// - `\W`: basename of the current directory (`~` for `$HOME`)
+/// - `\$`: `#` for root, `$` otherwise
+/// - `\t`: time as `HH:MM:SS`, `\A`: time as `HH:MM`
+/// - `\?`: exit status of the last command
+/// - `\n`: newline, `\\`: backslash
+/// - `\e` and `\NNN` (octal, e.g. `\033`): raw bytes for ANSI colors;
+///   `\a`: bell, which ends an `\e]` title sequence
+/// - `\[` and `\]`: delimit non-printing sequences; they produce no output
+///
+/// Unknown escapes are kept as-is.
+///
+/// # Example
+///
+/// ```
+/// let p = expand("\\[\\e[1m\\]\\W\\[\\e[0m\\]> ", "/tmp", 0);
+/// assert_eq!(p, "\x1B[1mtmp\x1B[0m> ");
+/// ```
+pub fn expand(template: &str, cwd: &str, status: i32) -> String {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+        } else {
+            expand_escape(&mut chars, &mut out, cwd, status);
+        }
+    }
+    out
+}
+
+/// Expands the escape after a backslash, taken from `chars`, onto `out`.
+fn expand_escape(chars: &mut Peekable<Chars>, out: &mut String, cwd: &str, status: i32) {
+    match chars.next() {
+        Some('u') => out.push_str(&user_name()),
+        Some('h') => {
+            let host = host_name();
+            out.push_str(host.split('.').next().unwrap_or_default());
+        }
+        Some('H') => out.push_str(&host_name()),
+        Some('w') => out.push_str(&tilde_abbrev(cwd)),
+        Some('W') => {
+            let short = tilde_abbrev(cwd);
+            if short == "~" || short == "/" {
+                out.push_str(&short);
+            } else {
+                let base = Path::new(cwd)
+                    .file_name()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| cwd.to_string());
+                out.push_str(&base);
+            }
+        }
+        Some('$') => out.push(if get_current_uid() == 0 { '#' } else { '$' }),
+        Some('t') => out.push_str(&chrono::Local::now().format("%H:%M:%S").to_string()),
+        Some('A') => out.push_str(&chrono::Local::now().format("%H:%M").to_string()),
+        Some('?') => out.push_str(&status.to_string()),
+        Some('n') => out.push('\n'),
+        Some('\\') => out.push('\\'),
+        Some('e') => out.push('\x1B'),
+        Some('a') => out.push('\x07'),
+        Some('[' | ']') => {}
+        Some(d) if d.is_digit(8) => {
+            let mut digits = d.to_string();
+            while digits.len() < 3 {
+                match chars.peek() {
+                    Some(&n) if n.is_digit(8) => {
+                        digits.push(n);
+                        chars.next();
+                    }
+                    _ => break,
+                }
+            }
+            match u8::from_str_radix(&digits, 8) {
+                Ok(byte) => out.push(byte as char),
+                Err(_) => {
+                    out.push('\\');
+                    out.push_str(&digits);
+                }
+            }
+        }
+        Some(other) => {
+            out.push('\\');
+            out.push(other);
+        }
+        None => out.push('\\'),
+    }
+}
+
+fn user_name() -> String {
+    get_current_username()
+        .map(|u| u.to_string_lossy().into_owned())
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+fn host_name() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|s| s.trim().to_string())
+        .or_else(|_| env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// Replaces a leading `$HOME` in `path` with `~`.
+pub fn tilde_abbrev(path: &str) -> String {
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() && home != "/" => {
+            if path == home {
+                "~".to_string()
+            } else if let Some(rest) = path.strip_prefix(&format!("{}/", home)) {
+                format!("~/{}", rest)
+            } else {
+                path.to_string()
+            }
+        }
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(template: &str) -> String {
+        expand(template, "/tmp/work", 0)
+    }
+
+    #[test]
+    fn expands_user_and_host() {
+        assert_eq!(text("\\u"), user_name());
+        assert_eq!(text("\\H"), host_name());
+        assert_eq!(text("\\h"), host_name().split('.').next().unwrap());
+    }
+
+    #[test]
+    fn expands_directories() {
+        assert_eq!(text("\\w"), tilde_abbrev("/tmp/work"));
+        assert_eq!(text("\\W"), "work");
+        assert_eq!(expand("\\W", "/", 0), "/");
+
+        let home = env::var("HOME").unwrap_or_default();
+        if home.len() > 1 {
+            let cwd = format!("{}/src", home);
+            assert_eq!(expand("\\w", &cwd, 0), "~/src");
+            assert_eq!(expand("\\W", &home, 0), "~");
+        }
+    }
+
+    #[test]
+    fn expands_prompt_sign_by_uid() {
+        let sign = if get_current_uid() == 0 { "#" } else { "$" };
+        assert_eq!(text("\\$ "), format!("{} ", sign));
+    }
+
+    #[test]
+    fn expands_time() {
+        let t = text("\\t");
+        assert_eq!(t.len(), 8);
+        assert_eq!((&t[2..3], &t[5..6]), (":", ":"));
+        assert!(t.chars().filter(|c| *c != ':').all(|c| c.is_ascii_digit()));
+        assert_eq!(text("\\A").len(), 5);
+    }
+
+    #[test]
+    fn expands_status() {
+        assert_eq!(expand("[\\?]", "/", 42), "[42]");
+        assert_eq!(text("\\?"), "0");
+    }
+
+    #[test]
+    fn keeps_unknown_escapes_and_expands_octal() {
+        assert_eq!(text("a\\qb\\\\"), "a\\qb\\");
+        assert_eq!(text("\\101\\n"), "A\n");
+    }
+
+    #[test]
+    fn expands_colors() {
+        let p = expand("\\[\\e[1;32m\\]\\W\\[\\e[0m\\]\\$ ", "/tmp/work", 0);
+        assert!(p.starts_with("\x1B[1;32mwork\x1B[0m"));
+        assert_eq!(text("\\033[31m>\\e[0m "), "\x1B[31m>\x1B[0m ");
+        assert_eq!(text("\\[\\e]0;title\\a\\]x "), "\x1B]0;title\x07x ");
+    }
+}
@@ -0,0 +1,77 @@
+// Each test crate uses a different subset of these helpers.
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// A scratch directory that is deleted, with its contents, when dropped.
+pub struct Scratch(PathBuf);
+
+impl Deref for Scratch {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        // Tests may leave read-only entries behind; removal is best effort.
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+/// Creates a fresh, empty scratch directory for a test.
+pub fn scratch(name: &str) -> Scratch {
+    let dir = std::env::temp_dir().join(format!("kinda_shell-{}-{}", name, std::process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    Scratch(dir)
+}
+
+/// Builds a shell process in `cwd` with an empty prompt, optionally limited
+/// to `memory_limit_kb` KiB of address space.
+pub fn shell(cwd: &Path, memory_limit_kb: Option<u64>) -> Command {
+    let bin = env!("CARGO_BIN_EXE_kinda_shell");
+    let mut cmd = match memory_limit_kb {
+        Some(kb) => {
+            let mut sh = Command::new("sh");
+            sh.arg("-c")
+                .arg(format!("ulimit -v {}; exec \"$0\"", kb))
+                .arg(bin);
+            sh
+        }
+        None => Command::new(bin),
+    };
+    cmd.current_dir(cwd)
+        .env("PS1", "")
+        .env("PS2", "")
+        .env("PWD", cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
+/// Feeds `script` to the shell's stdin and waits for it to exit.
+pub fn feed(mut cmd: Command, script: &str) -> Output {
+    let mut child = cmd.spawn().unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// Runs the shell in `cwd`, feeding it `script` on stdin.
+pub fn run(cwd: &Path, script: &str) -> Output {
+    feed(shell(cwd, None), script)
+}
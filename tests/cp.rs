@@ -0,0 +1,104 @@
+mod common;
+
+use common::{run, scratch};
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn copies_into_relative_directory_without_changing_cwd() {
+    let dir = scratch("cp-relative");
+    fs::write(dir.join("file.txt"), "hello").unwrap();
+    fs::create_dir(dir.join("backup")).unwrap();
+
+    let out = run(&dir, "cp file.txt backup\npwd\n");
+
+    assert_eq!(fs::read_to_string(dir.join("backup/file.txt")).unwrap(), "hello");
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), dir.display().to_string());
+    assert!(out.stderr.is_empty());
+}
+
+#[test]
+fn copies_into_absolute_directory() {
+    let dir = scratch("cp-absolute");
+    let target = scratch("cp-absolute-target");
+    fs::write(dir.join("file.txt"), "hello").unwrap();
+
+    run(&dir, &format!("cp file.txt {}\n", target.display()));
+
+    assert_eq!(fs::read_to_string(target.join("file.txt")).unwrap(), "hello");
+    assert!(!dir.join(target.file_name().unwrap()).exists());
+}
+
+#[test]
+fn copies_into_nested_directory() {
+    let dir = scratch("cp-nested");
+    fs::create_dir_all(dir.join("a/b/c")).unwrap();
+    fs::write(dir.join("a/file.txt"), "nested").unwrap();
+
+    run(&dir, "cp a/file.txt a/b/c/\n");
+
+    assert_eq!(fs::read_to_string(dir.join("a/b/c/file.txt")).unwrap(), "nested");
+    assert!(!dir.join("a/b/c/a").exists());
+    assert!(!dir.join("file.txt").exists());
+}
+
+#[test]
+fn refuses_to_copy_a_file_onto_itself() {
+    let dir = scratch("cp-same");
+    fs::write(dir.join("file.txt"), "keep me").unwrap();
+
+    let out = run(&dir, "cp file.txt file.txt\ncp file.txt .\n");
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(stderr.matches("are the same file").count(), 2);
+    assert_eq!(fs::read_to_string(dir.join("file.txt")).unwrap(), "keep me");
+}
+
+#[test]
+fn failed_copy_leaves_no_stray_file() {
+    let dir = scratch("cp-failed");
+    fs::create_dir(dir.join("backup")).unwrap();
+
+    // `/proc/self/mem` opens fine but fails on the first read, after the
+    // destination has been created.
+    let out = run(&dir, "cp /proc/self/mem backup\n");
+
+    assert!(!out.stderr.is_empty());
+    assert_eq!(fs::read_dir(dir.join("backup")).unwrap().count(), 0);
+}
+
+#[test]
+fn recursive_copy_continues_past_failures() {
+    let dir = scratch("cp-continue");
+    fs::create_dir_all(dir.join("src/sub")).unwrap();
+    fs::write(dir.join("src/a.txt"), "a").unwrap();
+    fs::write(dir.join("src/sub/z.txt"), "z").unwrap();
+    for fifo in ["src/pipe", "src/sub/pipe"] {
+        let made = Command::new("mkfifo").arg(dir.join(fifo)).status().unwrap();
+        assert!(made.success());
+    }
+
+    let out = run(&dir, "cp -r src dst\n");
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(stderr.matches("cp: cannot copy special file").count(), 2);
+    assert_eq!(fs::read_to_string(dir.join("dst/a.txt")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(dir.join("dst/sub/z.txt")).unwrap(), "z");
+}
+
+#[test]
+fn forced_copy_keeps_destination_when_source_is_unreadable() {
+    let dir = scratch("cp-force-unreadable");
+    fs::write(dir.join("dst"), "keep me").unwrap();
+
+    // This sysctl is write-only, so opening it for reading fails even
+    // for root.
+    let out = run(&dir, "cp -f /proc/sys/vm/compact_memory dst\n");
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(
+        stderr.trim(),
+        "cp: cannot open '/proc/sys/vm/compact_memory' for reading: Permission denied"
+    );
+    assert_eq!(fs::read_to_string(dir.join("dst")).unwrap(), "keep me");
+}
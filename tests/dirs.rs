@@ -0,0 +1,24 @@
+mod common;
+
+use common::{run, scratch};
+use std::fs;
+
+#[test]
+fn pushd_without_arguments_swaps_top_two_entries() {
+    let dir = scratch("dirs-swap");
+    for name in ["a", "b", "c"] {
+        fs::create_dir(dir.join(name)).unwrap();
+    }
+
+    let out = run(&dir, "pushd a\npushd ../b\npushd ../c\npushd\npwd\n");
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    let path = |name: &str| dir.join(name).display().to_string();
+    assert_eq!(
+        lines[3],
+        [path("b"), path("c"), path("a"), dir.display().to_string()].join(" ")
+    );
+    assert_eq!(lines[4], path("b"));
+    assert!(out.stderr.is_empty());
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{feed, scratch, shell};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::process::Stdio;
+
+/// Size of the test file: far larger than the memory the shell may use.
+const SIZE: u64 = 4 << 30;
+/// Address-space limit for the shell, in KiB.
+const LIMIT_KB: u64 = 256 << 10;
+
+/// Creates `big.img`, a sparse file of `SIZE` bytes with markers at both
+/// ends. It goes away with the scratch directory, even if the test fails.
+fn sparse_file(dir: &std::path::Path) {
+    let mut f = File::create(dir.join("big.img")).unwrap();
+    f.write_all(b"head").unwrap();
+    f.seek(SeekFrom::Start(SIZE - 4)).unwrap();
+    f.write_all(b"tail").unwrap();
+}
+
+#[test]
+fn cp_copies_multi_gigabyte_sparse_file_in_bounded_memory() {
+    let dir = scratch("large-cp");
+    sparse_file(&dir);
+
+    let out = feed(shell(&dir, Some(LIMIT_KB)), "cp big.img copy.img\n");
+
+    assert!(out.stderr.is_empty(), "{}", String::from_utf8_lossy(&out.stderr));
+    let copy = dir.join("copy.img");
+    let meta = fs::metadata(&copy).unwrap();
+    assert_eq!(meta.len(), SIZE);
+    assert!(meta.blocks() * 512 < SIZE / 2, "copy is not sparse");
+
+    let mut f = File::open(&copy).unwrap();
+    let mut buf = [0; 4];
+    f.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"head");
+    f.seek(SeekFrom::Start(SIZE - 4)).unwrap();
+    f.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"tail");
+}
+
+#[test]
+fn cat_streams_multi_gigabyte_file_in_bounded_memory() {
+    let dir = scratch("large-cat");
+    sparse_file(&dir);
+
+    let mut cmd = shell(&dir, Some(LIMIT_KB));
+    cmd.stdout(Stdio::null());
+    // The marker directory is only created if the shell survived `cat`.
+    let out = feed(cmd, "cat big.img\nmkdir done\n");
+
+    assert!(out.stderr.is_empty(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(dir.join("done").is_dir());
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::{run, scratch};
+use std::fs;
+use std::os::unix::fs::symlink;
+
+#[test]
+fn lists_entries_as_json() {
+    let dir = scratch("ls-json");
+    fs::write(dir.join("say \"hi\""), "hello").unwrap();
+    symlink("say \"hi\"", dir.join("link")).unwrap();
+
+    let out = run(&dir, "ls --format=json\n");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "[");
+    assert!(lines[1].starts_with(r#"  {"name": "link", "path": "./link", "type": "symlink", "#));
+    assert!(lines[1].ends_with(r#""target": "say \"hi\""},"#));
+    assert!(lines[2].starts_with(r#"  {"name": "say \"hi\"", "path": "./say \"hi\"", "type": "file", "#));
+    assert!(lines[2].contains(r#""size": 5, "major": null, "minor": null, "mtime": ""#));
+    assert!(lines[2].ends_with(r#""target": null}"#));
+    assert_eq!(lines[3], "]");
+    assert!(out.stderr.is_empty());
+}
+
+#[test]
+fn lists_entries_as_csv() {
+    let dir = scratch("ls-csv");
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/a,b"), "").unwrap();
+
+    let out = run(&dir, "ls --format=csv -R\n");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[0],
+        "name,path,type,permissions,links,user,group,size,major,minor,mtime,target"
+    );
+    assert!(lines[1].starts_with("sub,./sub,directory,d"));
+    assert!(lines[2].starts_with(r#""a,b","./sub/a,b",file,-"#));
+    assert!(lines[2].ends_with(','));
+}
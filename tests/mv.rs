@@ -0,0 +1,103 @@
+mod common;
+
+use common::{run, scratch};
+use std::fs;
+
+#[test]
+fn backs_up_existing_destination() {
+    let dir = scratch("mv-backup");
+    fs::write(dir.join("new.txt"), "new").unwrap();
+    fs::write(dir.join("old.txt"), "old").unwrap();
+
+    let out = run(&dir, "mv -b new.txt old.txt\n");
+
+    assert!(out.stderr.is_empty());
+    assert!(!dir.join("new.txt").exists());
+    assert_eq!(fs::read_to_string(dir.join("old.txt")).unwrap(), "new");
+    assert_eq!(fs::read_to_string(dir.join("old.txt~")).unwrap(), "old");
+}
+
+#[test]
+fn numbers_backups() {
+    let dir = scratch("mv-numbered");
+    for (name, text) in [("a", "a"), ("b", "b"), ("dst", "dst")] {
+        fs::write(dir.join(name), text).unwrap();
+    }
+
+    run(&dir, "mv --backup=numbered a dst\nmv --backup=numbered b dst\n");
+
+    assert_eq!(fs::read_to_string(dir.join("dst")).unwrap(), "b");
+    assert_eq!(fs::read_to_string(dir.join("dst.~1~")).unwrap(), "dst");
+    assert_eq!(fs::read_to_string(dir.join("dst.~2~")).unwrap(), "a");
+}
+
+#[test]
+fn restores_backup_when_move_fails() {
+    let dir = scratch("mv-restore");
+    fs::create_dir(dir.join("d")).unwrap();
+    fs::write(dir.join("d/x"), "keep").unwrap();
+
+    // A directory cannot be moved inside itself, so the rename fails after
+    // `d/x` has been backed up.
+    let out = run(&dir, "mv -b d d/x\n");
+
+    assert!(String::from_utf8_lossy(&out.stderr).contains("cannot move 'd' to 'd/x'"));
+    assert_eq!(fs::read_to_string(dir.join("d/x")).unwrap(), "keep");
+    assert!(!dir.join("d/x~").exists());
+}
+
+#[test]
+fn no_clobber_keeps_destination() {
+    let dir = scratch("mv-no-clobber");
+    fs::write(dir.join("a"), "a").unwrap();
+    fs::write(dir.join("b"), "b").unwrap();
+
+    run(&dir, "mv -n a b\n");
+
+    assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "b");
+}
+
+#[test]
+fn interactive_asks_before_overwriting() {
+    let dir = scratch("mv-interactive");
+    fs::write(dir.join("a"), "a").unwrap();
+    fs::write(dir.join("b"), "b").unwrap();
+
+    let out = run(&dir, "mv -i a b\nn\n");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("mv: overwrite 'b'?"));
+    assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "b");
+
+    run(&dir, "mv -i a b\ny\n");
+    assert!(!dir.join("a").exists());
+    assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "a");
+}
+
+#[test]
+fn moves_into_target_directory() {
+    let dir = scratch("mv-target");
+    fs::create_dir(dir.join("into")).unwrap();
+    fs::write(dir.join("a"), "a").unwrap();
+    fs::write(dir.join("b"), "b").unwrap();
+
+    let out = run(&dir, "mv -t into a b\n");
+
+    assert!(out.stderr.is_empty());
+    assert_eq!(fs::read_to_string(dir.join("into/a")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(dir.join("into/b")).unwrap(), "b");
+}
+
+#[test]
+fn continues_past_failed_sources() {
+    let dir = scratch("mv-continue");
+    fs::create_dir(dir.join("into")).unwrap();
+    fs::write(dir.join("a"), "a").unwrap();
+    fs::write(dir.join("file"), "").unwrap();
+
+    let out = run(&dir, "mv missing file/x a into\n");
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("mv: cannot stat 'missing': No such file or directory"));
+    assert!(stderr.contains("mv: cannot stat 'file/x': Not a directory"));
+    assert_eq!(fs::read_to_string(dir.join("into/a")).unwrap(), "a");
+}
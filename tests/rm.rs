@@ -0,0 +1,94 @@
+mod common;
+
+use common::{feed, run, scratch, shell};
+use std::fs;
+use std::os::unix::fs::symlink;
+
+#[test]
+fn force_ignores_only_missing_files() {
+    let dir = scratch("rm-force");
+    fs::write(dir.join("file"), "").unwrap();
+
+    let out = run(&dir, "rm -f missing\nrm -f file/x\n");
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(!stderr.contains("missing"));
+    assert!(stderr.contains("rm: cannot remove 'file/x': Not a directory"));
+}
+
+#[test]
+fn reports_missing_files_without_force() {
+    let dir = scratch("rm-missing");
+
+    let out = run(&dir, "rm missing\n");
+
+    assert_eq!(
+        String::from_utf8_lossy(&out.stderr),
+        "rm: cannot remove 'missing': No such file or directory\n"
+    );
+}
+
+#[test]
+fn trashes_and_restores_files() {
+    let dir = scratch("rm-trash");
+    let trash = dir.join("data/Trash");
+    fs::create_dir_all(trash.join("files")).unwrap();
+    fs::write(dir.join("notes.txt"), "notes").unwrap();
+    // A dangling symlink already in the trash must not be overwritten.
+    symlink("gone", trash.join("files/notes.txt")).unwrap();
+
+    let mut sh = shell(&dir, None);
+    sh.env("XDG_DATA_HOME", dir.join("data"));
+    let out = feed(sh, "rm --trash notes.txt\n");
+
+    assert!(out.stderr.is_empty());
+    assert!(!dir.join("notes.txt").exists());
+    assert!(fs::symlink_metadata(trash.join("files/notes.txt")).is_ok());
+    assert_eq!(
+        fs::read_to_string(trash.join("files/notes.txt_2")).unwrap(),
+        "notes"
+    );
+    let info = fs::read_to_string(trash.join("info/notes.txt_2.trashinfo")).unwrap();
+    assert!(info.contains(&format!("Path={}", dir.join("notes.txt").display())));
+
+    let mut sh = shell(&dir, None);
+    sh.env("XDG_DATA_HOME", dir.join("data"));
+    let out = feed(sh, "trash-restore notes.txt\n");
+
+    assert!(out.stderr.is_empty());
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "notes");
+    assert!(!trash.join("files/notes.txt_2").exists());
+    assert!(!trash.join("info/notes.txt_2.trashinfo").exists());
+}
+
+#[test]
+fn empties_the_trash() {
+    let dir = scratch("rm-trash-empty");
+    fs::write(dir.join("a"), "a").unwrap();
+    fs::create_dir(dir.join("d")).unwrap();
+
+    let mut sh = shell(&dir, None);
+    sh.env("XDG_DATA_HOME", dir.join("data"));
+    let out = feed(
+        sh,
+        "rm --trash a\nrm -r --trash d\ntrash-empty 1\ntrash-list\ntrash-empty\ntrash-list\n",
+    );
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(out.stderr.is_empty());
+    // `trash-empty 1` keeps both items, trashed less than a day ago.
+    assert_eq!(lines.len(), 2);
+    for name in ["a", "d"] {
+        let path = dir.join(name).display().to_string();
+        assert!(lines.iter().any(|l| l.ends_with(&path)), "{}", stdout);
+    }
+    assert_eq!(
+        fs::read_dir(dir.join("data/Trash/files")).unwrap().count(),
+        0
+    );
+    assert_eq!(
+        fs::read_dir(dir.join("data/Trash/info")).unwrap().count(),
+        0
+    );
+}